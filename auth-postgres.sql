@@ -14,7 +14,7 @@ CREATE TABLE identity (
 ALTER TABLE ONLY identity
     ADD CONSTRAINT identity_pkey PRIMARY KEY (id);
 
-CREATE UNIQUE INDEX identity_email_unique_index ON identity (email);
+CREATE UNIQUE INDEX identity_email_unique_index ON identity (lower(email));
 
 CREATE SEQUENCE identity_id_seq
     AS integer
@@ -42,7 +42,7 @@ ALTER TABLE ONLY token
     ADD CONSTRAINT token_pkey PRIMARY KEY (id);
 
 ALTER TABLE ONLY token
-    ADD CONSTRAINT token_identity_id_fkey FOREIGN KEY (identity_id) REFERENCES identity(id);
+    ADD CONSTRAINT token_identity_id_fkey FOREIGN KEY (identity_id) REFERENCES identity(id) ON DELETE CASCADE;
 
 CREATE VIEW token_active AS
     SELECT token.id,
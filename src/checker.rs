@@ -1,19 +1,84 @@
-use hyper::{Body, Method, Request, Response, StatusCode};
+use std::time::{Instant, SystemTime};
+
+use hyper::{Body, HeaderMap, Method, Request, Response, StatusCode};
+
+use crate::config::{Config, Csrf};
+use crate::db::Db;
+use crate::har::Exchange;
+use crate::spec::{Profile, Version};
+
+/// Length of the password checks register with, where the password policy
+/// allows it.
+const PASSWORD_LENGTH: u32 = 24;
+
+/// checks.txt items checked on every response rather than by a group.
+pub const COVERS: &[&str] = &[
+    "schema.declared",
+    "schema.strict",
+    "errors.code",
+    "errors.code-fallback",
+    "problem.content-type",
+    "problem.schema",
+    "problem.status",
+    "problem.type",
+    "problem.about-blank",
+];
 
 pub struct Checker {
     pub passed: u16,
     pub failed: u16,
+    pub skipped: u16,
     group: &'static str,
 
+    pub config: Config,
+    pub db: Option<Db>,
+    openapi: serde_json::Value,
     base_url: String,
 
-    pub path: &'static str,
+    pub path: String,
     method: Method,
-    expect_json: bool,
+    /// Off for requests whose response has no body, like GET /tokens/current/valid.
+    pub expect_json: bool,
+    pub response_status: StatusCode,
+    pub response_headers: HeaderMap,
+    pub response_body: Vec<u8>,
+
+    /// Secrets and passwords sent or received so far, which no response
+    /// should echo back.
+    sensitive: Vec<(String, &'static str)>,
+
+    /// How `*_with_token` requests send the token; authenticated groups run
+    /// once per transport the spec options allow.
+    pub transport: Transport,
+    /// Double-submit csrf cookies set on login, by token secret.
+    csrf_cookies: Vec<(String, String)>,
+    /// Random characters the test passwords are cut from, so they stay the
+    /// same when discovery changes the length limits.
+    password_characters: String,
+
+    /// Every request sent and its response, for --har.
+    pub exchanges: Vec<Exchange>,
 
     client: hyper::Client<hyper::client::HttpConnector, Body>,
 }
 
+/// An identity registered by `Checker::log_in`.
+pub struct Identity {
+    pub email: String,
+    /// As POST /users returned it.
+    pub id: Option<i64>,
+    /// (id, secret) of each token it logged in with.
+    pub tokens: Vec<(String, String)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Transport {
+    /// `cookie: token=<secret>`
+    Cookie,
+    /// `authorization: Bearer <secret>`, with the bearer profile
+    Bearer,
+}
+
 impl Checker {
     pub fn get_property_string(&mut self, json: &serde_json::Value, name: &'static str) -> Option<String> {
         if let Some(property_value) = json.get(name) {
@@ -51,112 +116,195 @@ impl Checker {
     }
 
     pub fn pass(&mut self, count: u16) {
-        self.passed = self.passed + count;
+        self.passed += count;
     }
 
     pub fn fail(&mut self, description: String) {
-        self.failed = self.failed + 1;
-        println!("Failed: {} {} - {} - {}", self.method, self.path, self.group, description);
+        self.failed += 1;
+        let transport = if self.transport == Transport::Bearer { " (bearer)" } else { "" };
+        println!("Failed: {} {}{} - {} - {}", self.method, self.path, transport, self.group, description);
+        if let Some(exchange) = self.exchanges.last_mut() {
+            exchange.failures.push(description);
+        }
+    }
+
+    pub fn skip(&mut self, group: &'static str, reason: &str) {
+        self.skipped += 1;
+        println!("Skipped: {} - {}", group, reason);
     }
 
     pub async fn get(&mut self, group: &'static str, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
         self.group = group;
         self.method = Method::GET;
-        let response = self.client.request(
+        let response = match self.send(
             Request::builder()
                 .method(Method::GET)
                 .uri(format!("{}{}", self.base_url, self.path))
                 .body(Body::empty())
                 .unwrap()
-        ).await.unwrap();
+        ).await {
+            Some(response) => response,
+            None => return (None, None),
+        };
 
         self.check_response(response, expected_status).await
     }
 
-    pub async fn get_with_token(&mut self, group: &'static str, token_secret: String, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
+    /// Like `get`, for a resource the server doesn't have to provide: a 404
+    /// returns `None` without failing.
+    pub async fn get_optional(&mut self, group: &'static str) -> Option<serde_json::Value> {
         self.group = group;
         self.method = Method::GET;
-        let response = self.client.request(
+        let response = match self.send(
             Request::builder()
                 .method(Method::GET)
                 .uri(format!("{}{}", self.base_url, self.path))
-                .header("cookie", format!("token={}", token_secret))
                 .body(Body::empty())
                 .unwrap()
-        ).await.unwrap();
+        ).await {
+            Some(response) => response,
+            None => return None,
+        };
 
-        self.check_response(response, expected_status).await
+        if response.status() == StatusCode::NOT_FOUND {
+            return None
+        }
+        let (json, _) = self.check_response(response, StatusCode::OK).await;
+        json
     }
 
-    pub async fn delete(&mut self, group: &'static str, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
+    pub async fn get_with_token(&mut self, group: &'static str, token_secret: String, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
+        self.request_with_token(group, Method::GET, token_secret, expected_status).await
+    }
+
+    pub async fn request_with_token(&mut self, group: &'static str, method: Method, token_secret: String, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
+        let headers = self.token_headers(&method, &token_secret);
+        self.request_with_headers(group, method, headers, None, Some(expected_status)).await
+    }
+
+    /// Like `request_with_token` with a body, of `content_type` if any.
+    pub async fn request_with_token_body(&mut self, group: &'static str, method: Method, token_secret: String, content_type: Option<&'static str>, body: String, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
+        let mut headers = self.token_headers(&method, &token_secret);
+        headers.extend(content_type.map(|content_type| ("content-type", content_type.to_string())));
+        self.request_with_headers(group, method, headers, Some(body), Some(expected_status)).await
+    }
+
+    /// The token as `transport` sends it. For the cookie, with the csrf
+    /// profile, also whatever marks a request that changes state as same-site.
+    pub fn token_headers(&self, method: &Method, token_secret: &str) -> Vec<(&'static str, String)> {
+        if self.transport == Transport::Bearer {
+            return vec![("authorization", format!("Bearer {}", token_secret))]
+        }
+        let mut cookie = format!("token={}", token_secret);
+        let mut headers = vec![];
+        let safe = *method == Method::GET || *method == Method::HEAD || *method == Method::OPTIONS;
+        if !safe && self.config.profiles.contains(&Profile::Csrf) {
+            match self.config.csrf {
+                Csrf::Origin => headers.push(("origin", self.origin())),
+                Csrf::Header => headers.push(("x-requested-with", "XMLHttpRequest".into())),
+                Csrf::DoubleSubmit => {
+                    if let Some((_, csrf)) = self.csrf_cookies.iter().find(|(secret, _)| secret == token_secret) {
+                        cookie = format!("{}; csrf={}", cookie, csrf);
+                        headers.push(("x-csrf-token", csrf.clone()));
+                    }
+                }
+            }
+        }
+        headers.insert(0, ("cookie", cookie));
+        headers
+    }
+
+    /// Scheme, host and port of the server, as a browser would send in origin.
+    pub fn origin(&self) -> String {
+        self.base_url.splitn(4, '/').take(3).collect::<Vec<&str>>().join("/")
+    }
+
+    /// Like `request_with_token` with any headers and an optional body.
+    /// Without an expected status any is accepted.
+    pub async fn request_with_headers(&mut self, group: &'static str, method: Method, headers: Vec<(&'static str, String)>, body: Option<String>, expected_status: Option<StatusCode>) -> (Option<serde_json::Value>, Option<String>) {
         self.group = group;
-        self.method = Method::DELETE;
-        let response = self.client.request(
-            Request::builder()
-                .method(Method::DELETE)
-                .uri(format!("{}{}", self.base_url, self.path))
-                .body(Body::empty())
-                .unwrap()
-        ).await.unwrap();
+        self.method = method.clone();
+        let expect_json = self.expect_json;
+        self.expect_json = expect_json && method != Method::HEAD && method != Method::OPTIONS;
+        let mut request = Request::builder()
+            .method(method)
+            .uri(format!("{}{}", self.base_url, self.path));
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        if let Some(body) = &body {
+            self.remember_request_password(body);
+        }
+        let response = match self.send(request.body(body.map(Body::from).unwrap_or_else(Body::empty)).unwrap()).await {
+            Some(response) => response,
+            None => {
+                self.expect_json = expect_json;
+                return (None, None)
+            }
+        };
 
-        self.check_response(response, expected_status).await
+        let expected_status = expected_status.unwrap_or_else(|| response.status());
+        let response = self.check_response(response, expected_status).await;
+        self.expect_json = expect_json;
+        response
     }
 
-    pub async fn delete_with_token(&mut self, group: &'static str, token_secret: String, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
+    pub async fn delete(&mut self, group: &'static str, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
         self.group = group;
         self.method = Method::DELETE;
-        let response = self.client.request(
+        let response = match self.send(
             Request::builder()
                 .method(Method::DELETE)
                 .uri(format!("{}{}", self.base_url, self.path))
-                .header("cookie", format!("token={}", token_secret))
                 .body(Body::empty())
                 .unwrap()
-        ).await.unwrap();
+        ).await {
+            Some(response) => response,
+            None => return (None, None),
+        };
 
         self.check_response(response, expected_status).await
     }
 
+    pub async fn delete_with_token(&mut self, group: &'static str, token_secret: String, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
+        self.request_with_token(group, Method::DELETE, token_secret, expected_status).await
+    }
+
     pub async fn post_no_body(&mut self, group: &'static str, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
         self.group = group;
         self.method = Method::POST;
-        let response = self.client.request(
+        let response = match self.send(
             Request::builder()
                 .method(Method::POST)
                 .uri(format!("{}{}", self.base_url, self.path))
                 .body(Body::empty())
                 .unwrap()
-        ).await.unwrap();
+        ).await {
+            Some(response) => response,
+            None => return (None, None),
+        };
 
         self.check_response(response, expected_status).await
     }
 
     pub async fn post_with_token(&mut self, group: &'static str, token_secret: String, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
-        self.group = group;
-        self.method = Method::POST;
-        let response = self.client.request(
-            Request::builder()
-                .method(Method::POST)
-                .uri(format!("{}{}", self.base_url, self.path))
-                .header("cookie", format!("token={}", token_secret))
-                .body(Body::empty())
-                .unwrap()
-        ).await.unwrap();
-
-        self.check_response(response, expected_status).await
+        self.request_with_token(group, Method::POST, token_secret, expected_status).await
     }
 
     pub async fn post_bad_content_type(&mut self, group: &'static str, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
         self.group = group;
         self.method = Method::POST;
-        let response = self.client.request(
+        let response = match self.send(
             Request::builder()
                 .method(Method::POST)
                 .uri(format!("{}{}", self.base_url, self.path))
                 .header("content-type", "image/png")
                 .body(Body::empty())
                 .unwrap()
-        ).await.unwrap();
+        ).await {
+            Some(response) => response,
+            None => return (None, None),
+        };
 
         self.check_response(response, expected_status).await
     }
@@ -164,14 +312,18 @@ impl Checker {
     pub async fn post(&mut self, group: &'static str, body: String, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
         self.group = group;
         self.method = Method::POST;
-        let response = self.client.request(
+        self.remember_request_password(&body);
+        let response = match self.send(
             Request::builder()
                 .method(Method::POST)
                 .uri(format!("{}{}", self.base_url, self.path))
                 .header("content-type", "application/json")
                 .body(Body::from(body))
                 .unwrap()
-        ).await.unwrap();
+        ).await {
+            Some(response) => response,
+            None => return (None, None),
+        };
 
         self.check_response(response, expected_status).await
     }
@@ -179,14 +331,18 @@ impl Checker {
     pub async fn post_content_type(&mut self, group: &'static str, content_type: &'static str, body: String, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
         self.group = group;
         self.method = Method::POST;
-        let response = self.client.request(
+        self.remember_request_password(&body);
+        let response = match self.send(
             Request::builder()
                 .method(Method::POST)
                 .uri(format!("{}{}", self.base_url, self.path))
                 .header("content-type", content_type)
                 .body(Body::from(body))
                 .unwrap()
-        ).await.unwrap();
+        ).await {
+            Some(response) => response,
+            None => return (None, None),
+        };
 
         self.check_response(response, expected_status).await
     }
@@ -194,30 +350,161 @@ impl Checker {
     pub async fn post_no_content_type(&mut self, group: &'static str, body: String, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
         self.group = group;
         self.method = Method::POST;
-        let response = self.client.request(
+        self.remember_request_password(&body);
+        let response = match self.send(
             Request::builder()
                 .method(Method::POST)
                 .uri(format!("{}{}", self.base_url, self.path))
                 .body(Body::from(body))
                 .unwrap()
-        ).await.unwrap();
+        ).await {
+            Some(response) => response,
+            None => return (None, None),
+        };
 
         self.check_response(response, expected_status).await
     }
 
+    /// The password every check registers with. It's random, so no password
+    /// policy rejects it as weak or breached, and as long as the policy's
+    /// limits allow up to `PASSWORD_LENGTH`.
+    pub fn password(&self) -> String {
+        self.other_password(0)
+    }
+
+    /// A password as strong as `password` but different, for changing to or
+    /// logging in with wrongly; `n` from 1 to 9 picks which.
+    pub fn other_password(&self, n: u32) -> String {
+        let length = password_length(&self.config).clamp(1, self.password_characters.len());
+        format!("{}{}", &self.password_characters[..length - 1], n)
+    }
+
+    /// POST /users for `email` and `password`.
+    pub async fn register(&mut self, group: &'static str, email: &str, password: &str, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
+        self.path = "/users".into();
+        self.post(group, serde_json::json!({ "email": email, "password": password }).to_string(), expected_status).await
+    }
+
+    /// POST /tokens for `email` and `password`, for a token that doesn't expire.
+    pub async fn create_token(&mut self, group: &'static str, email: &str, password: &str, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
+        self.path = "/tokens".into();
+        let body = serde_json::json!({ "email": email, "password": password, "lifetime": "no-expiration" });
+        self.post(group, body.to_string(), expected_status).await
+    }
+
+    /// Registers a new identity and logs in to it `n` times. Fails and returns
+    /// `None` when a login doesn't hand out a token.
+    pub async fn log_in(&mut self, group: &'static str, n: usize) -> Option<Identity> {
+        let email = new_email();
+        let password = self.password();
+        let (json_response, _) = self.register(group, &email, &password, StatusCode::OK).await;
+        let id = json_response.and_then(|json| json["id"].as_i64());
+
+        let mut tokens = vec![];
+        for _ in 0..n {
+            let (json_response, _) = self.create_token(group, &email, &password, StatusCode::OK).await;
+            if let Some(token) = json_response.and_then(|json| Some((json["id"].as_str()?.to_string(), json["secret"].as_str()?.to_string()))) {
+                tokens.push(token);
+            }
+        }
+        if tokens.len() < n {
+            self.fail(format!("could not log in as '{}'", email));
+            return None
+        }
+        Some(Identity { email, id, tokens })
+    }
+
+    /// Sends a request, recording it and the response in `exchanges`.
+    /// Sends the request and records the exchange. If the server can't be
+    /// reached or the response can't be read, fails and returns `None`; the
+    /// exchange is recorded with status 0, as HAR does for no response.
+    async fn send(&mut self, request: Request<Body>) -> Option<Response<Body>> {
+        let (parts, body) = request.into_parts();
+        let request_body = hyper::body::to_bytes(body).await.unwrap().to_vec();
+        let (method, url, request_headers) = (parts.method.clone(), parts.uri.to_string(), parts.headers.clone());
+        let started = SystemTime::now();
+        let start = Instant::now();
+        let response = self.client.request(Request::from_parts(parts, Body::from(request_body.clone()))).await;
+        let response = match response {
+            Ok(response) => {
+                let (response_parts, response_body) = response.into_parts();
+                hyper::body::to_bytes(response_body).await.map(|response_body| (response_parts, response_body.to_vec()))
+            }
+            Err(e) => Err(e),
+        };
+
+        let mut exchange = Exchange {
+            started,
+            time: start.elapsed(),
+            group: self.group,
+            method,
+            url,
+            request_headers,
+            request_body,
+            status: 0,
+            response_headers: HeaderMap::new(),
+            response_body: vec![],
+            failures: vec![],
+        };
+        match response {
+            Ok((response_parts, response_body)) => {
+                exchange.status = response_parts.status.as_u16();
+                exchange.response_headers = response_parts.headers.clone();
+                exchange.response_body = response_body.clone();
+                self.exchanges.push(exchange);
+                Some(Response::from_parts(response_parts, Body::from(response_body)))
+            }
+            Err(e) => {
+                self.exchanges.push(exchange);
+                self.fail(format!("no response: {}", e));
+                None
+            }
+        }
+    }
+
     pub async fn check_response(&mut self, response: Response<Body>, expected_status: StatusCode) -> (Option<serde_json::Value>, Option<String>) {
         let status = response.status();
+        self.response_status = status;
+        self.response_headers = response.headers().clone();
+        self.response_body = hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec();
         self.check(
             status == expected_status,
             format!("should have returned '{}' but returned '{}'", expected_status, status),
         );
+        // leaks are a v2 rule, like the leaks group
+        if self.config.spec_version >= Version::V2 {
+            self.check_leaks();
+        }
 
         let json = if self.expect_json {
-            self.check_json_content_type(response).await
+            self.check_json_content_type()
         } else {
             None
         };
 
+        if let Some(secret) = json.as_ref().and_then(|json| json["secret"].as_str()) {
+            if self.is_token_create() {
+                self.remember_sensitive(secret.to_string(), "token secret");
+                let csrf = self.response_headers.get_all("set-cookie").iter()
+                    .filter_map(|cookie| cookie.to_str().ok()?.split(';').next()?.trim().strip_prefix("csrf="))
+                    .next()
+                    .map(|csrf| csrf.to_string());
+                if let Some(csrf) = csrf {
+                    self.csrf_cookies.push((secret.to_string(), csrf));
+                }
+            }
+        }
+
+        if let Some(json) = &json {
+            self.check_schema(json);
+            if self.is_problem() {
+                self.check(
+                    json["status"].as_u64() == Some(status.as_u16() as u64),
+                    format!("problem 'status' is {} but the response is {}", json["status"], status.as_u16()),
+                );
+            }
+        }
+
         if json.is_some() {
             (json, None)
         } else {
@@ -225,24 +512,130 @@ impl Checker {
         }
     }
 
+    pub fn remember_sensitive(&mut self, value: String, kind: &'static str) {
+        if !self.sensitive.iter().any(|(known, _)| *known == value) {
+            self.sensitive.push((value, kind));
+        }
+    }
+
+    /// Short passwords like "password" also turn up in ordinary error
+    /// messages, so only distinctive ones are tracked.
+    fn remember_request_password(&mut self, body: &str) {
+        if let Ok(json) = serde_json::from_str::<serde_json::Value>(body) {
+            for name in &["password", "current_password", "new_password"] {
+                if let Some(password) = json[name].as_str() {
+                    if password.chars().count() >= 12 {
+                        self.remember_sensitive(password.to_string(), "password");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether the last response is an error that the problem-json profile
+    /// requires to be RFC 7807 problem details.
+    fn is_problem(&self) -> bool {
+        self.response_status.as_u16() >= 400 && self.config.profiles.contains(&Profile::ProblemJson)
+    }
+
+    fn is_token_create(&self) -> bool {
+        self.method == Method::POST && self.path == "/tokens" && self.response_status == StatusCode::OK
+    }
+
+    /// Fails for every remembered secret or password found in the last
+    /// response. Token secrets may only appear in the `POST /tokens` body that
+    /// creates them and in a `token` set-cookie header.
+    pub fn check_leaks(&mut self) {
+        let body = String::from_utf8_lossy(&self.response_body).to_string();
+        let mut leaks = vec![];
+
+        for (value, kind) in &self.sensitive {
+            // the secret a create response returns is only remembered after this runs
+            if body.contains(value.as_str()) {
+                leaks.push(format!("response body contains a {}", kind));
+            }
+            for (name, header) in &self.response_headers {
+                let header = String::from_utf8_lossy(header.as_bytes());
+                let is_token_cookie = name == "set-cookie" && header.trim_start().starts_with("token=");
+                if header.contains(value.as_str()) && !(*kind == "token secret" && is_token_cookie) {
+                    leaks.push(format!("'{}' header contains a {}", name, kind));
+                }
+            }
+        }
+
+        for prefix in &["$2a$", "$2b$", "$2y$", "$argon2", "$scrypt$", "$pbkdf2", "pbkdf2_sha"] {
+            if body.contains(prefix) {
+                leaks.push(format!("response body contains what looks like a password hash ('{}')", prefix));
+            }
+        }
+
+        if leaks.is_empty() {
+            self.pass(1);
+        }
+        for leak in leaks {
+            self.fail(leak);
+        }
+    }
+
+    /// Validates a json response against the schema the spec declares for
+    /// its path, method and status.
+    pub fn check_schema(&mut self, json: &serde_json::Value) {
+        let media_type = if self.is_problem() { "application/problem+json" } else { "application/json" };
+        let schema = crate::schema::response_schema(&self.openapi, &self.method, &self.path, self.response_status, media_type);
+        let errors = match schema {
+            Ok(Some(schema)) => crate::schema::validate(&self.openapi, schema, json, self.config.strict_schema),
+            Ok(None) => vec!["spec does not declare a body for this response".into()],
+            Err(e) => vec![e],
+        };
+
+        if errors.is_empty() {
+            self.pass(1);
+        }
+        for error in errors {
+            self.fail(format!("schema: {}", error));
+        }
+    }
+
     pub fn check_error_response(
         &mut self,
         response: (Option<serde_json::Value>, Option<String>),
+        code: &str,
         needle: &str,
     ) {
-        self.check_error_response_multi(response, vec![needle])
+        self.check_error_response_multi(response, code, vec![needle])
     }
 
-
+    /// Checks the error case of an error response: exactly by its `code`
+    /// when the server sends one, otherwise by the `error` message (or body)
+    /// mentioning one of `needles`. Problem details are checked by their
+    /// `type`, or their `title` and `detail` when it's about:blank.
     pub fn check_error_response_multi(
         &mut self,
         response: (Option<serde_json::Value>, Option<String>),
+        code: &str,
         needles: Vec<&str>,
     ) {
         let (json_response, other_response) = response;
-        if let Some(json_response) = json_response {
-            if let Some(error) = self.get_property_string(&json_response, "error") {
-                self.check_contains_one("json 'error' property", error, needles);
+        if let Some(json_response) = json_response.as_ref().filter(|_| self.is_problem()) {
+            let problem_type = json_response["type"].as_str().unwrap_or("about:blank");
+            if problem_type != "about:blank" {
+                self.check(
+                    problem_type.rsplit('/').next() == Some(code),
+                    format!("problem 'type' is '{}' instead of a URI ending in '/{}'", problem_type, code),
+                );
+            } else {
+                let message = format!("{} {}", json_response["title"].as_str().unwrap_or(""), json_response["detail"].as_str().unwrap_or(""));
+                self.check_contains_one("problem 'title' and 'detail'", message, needles);
+            }
+        } else if let Some(json_response) = json_response {
+            match json_response.get("code") {
+                Some(serde_json::Value::String(actual)) => {
+                    self.check(actual == code, format!("json 'code' property is '{}' instead of '{}'", actual, code));
+                }
+                Some(_) => self.fail("json 'code' property is not a string".into()),
+                None => if let Some(error) = self.get_property_string(&json_response, "error") {
+                    self.check_contains_one("json 'error' property", error, needles);
+                },
             }
         } else if let Some(other_response) = other_response {
             self.check_contains_one("body", other_response, needles);
@@ -255,12 +648,12 @@ impl Checker {
         let haystack = haystack.to_lowercase();
         if needles.len() == 1 {
             return self.check(
-                haystack.contains(&needles[0]),
+                haystack.contains(needles[0]),
                 format!("{} does not mention '{}': '{}'", prefix, needles[0], haystack)
             )
         }
         for needle in needles.clone() {
-            if haystack.contains(&needle) {
+            if haystack.contains(needle) {
                 self.pass(1);
                 return true
             }
@@ -269,8 +662,17 @@ impl Checker {
         false
     }
 
-    pub async fn check_json_content_type(&mut self, response: Response<Body>) -> Option<serde_json::Value> {
-        match response.headers().get("content-type") {
+    /// The items of a comma separated response header, possibly repeated.
+    pub fn response_header_list(&self, name: &str) -> Vec<String> {
+        self.response_headers.get_all(name)
+            .iter()
+            .flat_map(|value| value.to_str().unwrap_or("").split(',').map(|item| item.trim().to_string()).collect::<Vec<String>>())
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+
+    pub fn check_json_content_type(&mut self) -> Option<serde_json::Value> {
+        match self.response_headers.get("content-type").cloned() {
             None => {
                 self.fail("missing content-type".into());
                 None
@@ -280,12 +682,19 @@ impl Checker {
 
                 let content_type = content_type.to_str().unwrap();
                 let content_type_parts: Vec<&str> = content_type.split(';').collect();
+                let media_type = if self.is_problem() { "application/problem+json" } else { "application/json" };
 
                 if !self.check(
-                    content_type_parts[0] == "application/json",
-                    format!("content-type is '{}' instead of application/json", content_type),
+                    content_type_parts[0] == media_type,
+                    format!("content-type is '{}' instead of {}", content_type, media_type),
                 ) {
                     return None
+                } else if self.is_problem() {
+                    // problem+json is always utf-8, so the charset is optional
+                    self.check(
+                        content_type_parts.len() == 1 || (content_type_parts.len() == 2 && content_type_parts[1] == "charset=utf-8"),
+                        "content-type has parameters other than 'charset=utf-8'".into(),
+                    );
                 } else {
                     self.check(
                         content_type_parts.len() == 2 && content_type_parts[1] == "charset=utf-8",
@@ -293,9 +702,7 @@ impl Checker {
                     );
                 }
 
-                let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
-
-                match serde_json::from_slice(body.to_vec().as_slice()) {
+                match serde_json::from_slice(self.response_body.as_slice()) {
                     Ok(json) => {
                         self.pass(1);
                         Some(json)
@@ -303,7 +710,7 @@ impl Checker {
                     Err(e) => {
                         self.fail("could not parse response as json".into());
                         println!("error: {:?}", e);
-                        println!("body: {:?}", body);
+                        println!("body: {:?}", String::from_utf8_lossy(&self.response_body));
 
                         None
                     }
@@ -313,18 +720,49 @@ impl Checker {
     }
 
 
-    pub fn new(base_url: String) -> Checker {
+    pub fn new(config: Config) -> Checker {
         Checker {
             passed: 0,
             failed: 0,
+            skipped: 0,
             group: "",
 
-            base_url,
-            path: "/",
+            db: config.db_url.clone().map(Db::new),
+            openapi: crate::openapi::document(),
+            base_url: config.base_url.clone(),
+            config,
+            path: "/".into(),
             method: Method::GET,
             expect_json: true,
+            response_status: StatusCode::OK,
+            response_headers: HeaderMap::new(),
+            response_body: vec![],
 
+            sensitive: vec![],
+
+            transport: Transport::Cookie,
+            csrf_cookies: vec![],
+            password_characters: random_password(4096),
+            exchanges: vec![],
             client: hyper::Client::new(),
         }
     }
 }
+
+/// An email no identity has registered yet.
+pub fn new_email() -> String {
+    format!("test+{:0>8x}@example.com", rand::random::<u32>())
+}
+
+/// How long a password checks register with should be for `config`'s
+/// password policy.
+pub fn password_length(config: &Config) -> usize {
+    PASSWORD_LENGTH.max(config.password_min_length).min(config.password_max_length) as usize
+}
+
+/// `length` random letters and digits.
+pub fn random_password(length: usize) -> String {
+    use rand::Rng;
+
+    rand::thread_rng().sample_iter(&rand::distributions::Alphanumeric).take(length).collect()
+}
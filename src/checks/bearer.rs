@@ -0,0 +1,67 @@
+use hyper::{Method, StatusCode};
+
+pub const COVERS: &[&str] = &[
+    "bearer.precedence",
+    "bearer.no-fallback",
+    "bearer.scheme-case",
+    "bearer.www-authenticate",
+];
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    let tokens = match c.log_in("bearer; log in", 2).await {
+        Some(identity) => identity.tokens,
+        None => return,
+    };
+    let (cookie_id, cookie_secret) = tokens[0].clone();
+    let (bearer_id, bearer_secret) = tokens[1].clone();
+
+    c.path = "/tokens/current".into();
+    let (json_response, _) = c.request_with_headers(
+        "bearer; header and cookie with different tokens",
+        Method::GET,
+        vec![
+            ("cookie", format!("token={}", cookie_secret)),
+            ("authorization", format!("Bearer {}", bearer_secret)),
+        ],
+        None,
+        Some(StatusCode::OK),
+    ).await;
+    if let Some(id) = json_response.as_ref().and_then(|json| json["id"].as_str()) {
+        c.check(
+            id == bearer_id,
+            format!("current token is {} but the authorization header's is {} (the cookie's is {})", id, bearer_id, cookie_id),
+        );
+    }
+
+    c.request_with_headers(
+        "bearer; invalid header with a valid cookie",
+        Method::GET,
+        vec![
+            ("cookie", format!("token={}", cookie_secret)),
+            ("authorization", format!("Bearer {}x", bearer_secret)),
+        ],
+        None,
+        Some(StatusCode::UNAUTHORIZED),
+    ).await;
+
+    c.request_with_headers(
+        "bearer; lowercase scheme",
+        Method::GET,
+        vec![("authorization", format!("bearer {}", bearer_secret))],
+        None,
+        Some(StatusCode::OK),
+    ).await;
+
+    c.request_with_headers(
+        "bearer; invalid token",
+        Method::GET,
+        vec![("authorization", format!("Bearer {}x", bearer_secret))],
+        None,
+        Some(StatusCode::UNAUTHORIZED),
+    ).await;
+    let www_authenticate = c.response_headers.get("www-authenticate").map(|value| value.to_str().unwrap_or("").to_string());
+    c.check(
+        www_authenticate.as_ref().is_some_and(|value| value.to_lowercase().starts_with("bearer")),
+        format!("401 for a bearer token has www-authenticate {:?} instead of a Bearer challenge", www_authenticate),
+    );
+}
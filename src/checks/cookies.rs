@@ -0,0 +1,279 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hyper::StatusCode;
+
+const REMEMBER_ME_SECONDS: i64 = 7 * 24 * 60 * 60;
+const CLOCK_SKEW_SECONDS: i64 = 60;
+
+pub const COVERS: &[&str] = &[
+    "cookie.present",
+    "cookie.http-only",
+    "cookie.secure",
+    "cookie.same-site",
+    "cookie.path",
+    "cookie.until-idle",
+    "cookie.remember-me",
+    "cookie.no-expiration",
+    "cookie.cleared",
+];
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    let email_1 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
+
+    c.path = "/users".into();
+    c.post(
+        "set-cookie; create",
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, c.password()),
+        StatusCode::OK,
+    ).await;
+
+    c.path = "/tokens".into();
+    let mut token_secret = None;
+    for lifetime in &["until-idle", "remember-me", "no-expiration"] {
+        if !c.config.lifetimes.iter().any(|supported| supported == lifetime) {
+            c.skip("set-cookie on create", &format!("lifetime '{}' is not supported", lifetime));
+            continue
+        }
+        let (json_response, _) = c.post(
+            "set-cookie on create",
+            format!(r#"{{"email":"{}", "password": "{}", "lifetime": "{}" }}"#, email_1, c.password(), lifetime),
+            StatusCode::OK,
+        ).await;
+        let secret = match json_response {
+            Some(json_response) => c.get_property_string(&json_response, "secret"),
+            None => {
+                c.fail("token create response was not json".into());
+                None
+            }
+        };
+
+        if let Some(cookie) = check_token_cookie(c, secret.as_deref()) {
+            check_cookie_lifetime(c, &cookie, lifetime);
+        }
+        if *lifetime == "no-expiration" {
+            token_secret = secret;
+        }
+    }
+
+    if let Some(token_secret) = token_secret {
+        c.path = "/tokens/current/refresh".into();
+        c.post_with_token("set-cookie on refresh", token_secret.clone(), StatusCode::OK).await;
+        if let Some(cookie) = check_token_cookie(c, Some(&token_secret)) {
+            check_cookie_lifetime(c, &cookie, "no-expiration");
+        }
+
+        c.path = "/tokens/current".into();
+        c.delete_with_token("set-cookie cleared on delete", token_secret, StatusCode::OK).await;
+        if let Some(cookie) = find_token_cookie(c) {
+            let now = now();
+            let cleared = cookie.max_age.is_some_and(|max_age| max_age <= 0)
+                || cookie.expires.is_some_and(|expires| expires <= now);
+            c.check(
+                cleared,
+                format!("token cookie is not cleared with Max-Age=0 or an Expires in the past: '{}'", cookie.raw),
+            );
+        }
+    }
+}
+
+/// Checks the attributes every `token` cookie must have and returns it for
+/// lifetime specific checks.
+fn check_token_cookie(c: &mut crate::checker::Checker, secret: Option<&str>) -> Option<Cookie> {
+    let cookie = find_token_cookie(c)?;
+
+    if let Some(secret) = secret {
+        c.check(
+            cookie.value == secret,
+            format!("token cookie value '{}' does not match the token secret '{}'", cookie.value, secret),
+        );
+    }
+    c.check(cookie.http_only, format!("token cookie is missing HttpOnly: '{}'", cookie.raw));
+    if !c.config.allow_insecure_cookie {
+        c.check(cookie.secure, format!("token cookie is missing Secure: '{}'", cookie.raw));
+    }
+    match cookie.same_site.as_deref() {
+        Some("strict") | Some("lax") => c.pass(1),
+        Some("none") => {
+            c.check(cookie.secure, format!("token cookie has SameSite=None without Secure: '{}'", cookie.raw));
+        }
+        _ => c.fail(format!("token cookie is missing SameSite=Strict or Lax: '{}'", cookie.raw)),
+    }
+    c.check(
+        cookie.path.as_deref() == Some("/"),
+        format!("token cookie should have Path=/: '{}'", cookie.raw),
+    );
+
+    Some(cookie)
+}
+
+fn check_cookie_lifetime(c: &mut crate::checker::Checker, cookie: &Cookie, lifetime: &str) {
+    let now = now();
+    let expires_in = cookie.max_age.or_else(|| cookie.expires.map(|expires| expires - now));
+
+    match (lifetime, expires_in) {
+        ("until-idle", expires_in) => {
+            c.check(
+                expires_in.is_none(),
+                format!("until-idle token cookie should be a session cookie without Max-Age or Expires: '{}'", cookie.raw),
+            );
+        }
+        ("remember-me", Some(expires_in)) => {
+            c.check(
+                (expires_in - REMEMBER_ME_SECONDS).abs() <= CLOCK_SKEW_SECONDS,
+                format!("remember-me token cookie should expire in 7 days, expires in {}s: '{}'", expires_in, cookie.raw),
+            );
+        }
+        ("no-expiration", Some(expires_in)) => {
+            c.check(
+                expires_in >= REMEMBER_ME_SECONDS,
+                format!("no-expiration token cookie should outlive remember-me, expires in {}s: '{}'", expires_in, cookie.raw),
+            );
+        }
+        (lifetime, None) => {
+            c.fail(format!("{} token cookie should have Max-Age or Expires: '{}'", lifetime, cookie.raw));
+        }
+        _ => {}
+    }
+}
+
+fn find_token_cookie(c: &mut crate::checker::Checker) -> Option<Cookie> {
+    let cookies: Vec<Cookie> = c.response_headers
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|header| header.to_str().ok())
+        .map(Cookie::parse)
+        .filter(|cookie| cookie.name == "token")
+        .collect();
+
+    match cookies.len() {
+        0 => {
+            c.fail("response has no 'token' set-cookie header".into());
+            None
+        }
+        1 => {
+            c.pass(1);
+            cookies.into_iter().next()
+        }
+        count => {
+            c.fail(format!("response sets the 'token' cookie {} times", count));
+            None
+        }
+    }
+}
+
+struct Cookie {
+    raw: String,
+    name: String,
+    value: String,
+    http_only: bool,
+    secure: bool,
+    same_site: Option<String>,
+    path: Option<String>,
+    max_age: Option<i64>,
+    expires: Option<i64>,
+}
+
+impl Cookie {
+    fn parse(header: &str) -> Cookie {
+        let mut parts = header.split(';').map(|part| part.trim());
+        let (name, value) = split_pair(parts.next().unwrap_or(""));
+        let mut cookie = Cookie {
+            raw: header.to_string(),
+            name,
+            value,
+            http_only: false,
+            secure: false,
+            same_site: None,
+            path: None,
+            max_age: None,
+            expires: None,
+        };
+
+        for attribute in parts {
+            let (name, value) = split_pair(attribute);
+            match name.to_lowercase().as_str() {
+                "httponly" => cookie.http_only = true,
+                "secure" => cookie.secure = true,
+                "samesite" => cookie.same_site = Some(value.to_lowercase()),
+                "path" => cookie.path = Some(value),
+                "max-age" => cookie.max_age = value.parse().ok(),
+                "expires" => cookie.expires = parse_http_date(&value),
+                _ => {}
+            }
+        }
+
+        cookie
+    }
+}
+
+fn split_pair(pair: &str) -> (String, String) {
+    match pair.split_once('=') {
+        Some((name, value)) => (name.trim().to_string(), value.trim().to_string()),
+        None => (pair.trim().to_string(), "".into()),
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+}
+
+/// Parses an IMF-fixdate (`Sun, 06 Nov 1994 08:49:37 GMT`), also accepting the
+/// dashes browsers tolerate (`06-Nov-1994`), into unix seconds.
+fn parse_http_date(date: &str) -> Option<i64> {
+    let date = date.split_once(',').map_or(date, |(_, date)| date).replace('-', " ");
+    let parts: Vec<&str> = date.split_whitespace().collect();
+    if parts.len() != 5 {
+        return None
+    }
+
+    let day: i64 = parts[0].parse().ok()?;
+    let month = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"]
+        .iter()
+        .position(|month| parts[1].to_lowercase() == *month)? as i64 + 1;
+    let mut year: i64 = parts[2].parse().ok()?;
+    if year < 100 {
+        year += if year < 70 { 2000 } else { 1900 };
+    }
+    let time: Vec<i64> = parts[3].split(':').filter_map(|part| part.parse().ok()).collect();
+    if time.len() != 3 {
+        return None
+    }
+
+    // days from civil, http://howardhinnant.github.io/date_algorithms.html
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    Some(days * 86_400 + time[0] * 3_600 + time[1] * 60 + time[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_http_date;
+
+    #[test]
+    fn parse_http_date_reads_imf_fixdate() {
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(784_111_777));
+        assert_eq!(parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0));
+        assert_eq!(parse_http_date("Tue, 29 Feb 2000 12:00:00 GMT"), Some(951_825_600));
+    }
+
+    #[test]
+    fn parse_http_date_accepts_browser_variants() {
+        assert_eq!(parse_http_date("Sun, 06-Nov-1994 08:49:37 GMT"), Some(784_111_777));
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), Some(784_111_777));
+        assert_eq!(parse_http_date("Fri, 01 JAN 2038 00:00:00 GMT"), Some(2_145_916_800));
+    }
+
+    #[test]
+    fn parse_http_date_rejects_malformed_dates() {
+        assert_eq!(parse_http_date(""), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Foo 1994 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49 GMT"), None);
+        assert_eq!(parse_http_date("784111777"), None);
+    }
+}
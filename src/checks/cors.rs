@@ -0,0 +1,146 @@
+use hyper::{Method, StatusCode};
+
+use crate::config::Csrf;
+use crate::spec::Profile;
+
+pub const COVERS: &[&str] = &[
+    "cors.preflight",
+    "cors.preflight-methods",
+    "cors.preflight-headers",
+    "cors.credentials",
+    "cors.vary",
+    "cors.disallowed",
+];
+
+/// An origin no server is configured to allow.
+const DISALLOWED_ORIGIN: &str = "https://disallowed.example";
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    if c.config.cors_origins.is_empty() {
+        c.skip("cors", "no --cors-origin given");
+        return
+    }
+
+    let (token_id, secret) = match c.log_in("cors; log in", 1).await {
+        Some(identity) => identity.tokens[0].clone(),
+        None => return,
+    };
+
+    let document = crate::openapi::document();
+    let origins = c.config.cors_origins.clone();
+    for (template, operations) in document["paths"].as_object().into_iter().flatten() {
+        if template.starts_with("/.well-known/") || crate::openapi::since(operations) > c.config.spec_version {
+            continue
+        }
+        c.path = template.replace("{id}", &token_id);
+
+        for method in crate::openapi::methods(operations) {
+            let request_headers = request_headers(c, &method, operations);
+            let mut headers = vec![("access-control-request-method", method.to_string())];
+            if !request_headers.is_empty() {
+                headers.push(("access-control-request-headers", request_headers.join(",")));
+            }
+
+            for origin in &origins {
+                let mut preflight = headers.clone();
+                preflight.push(("origin", origin.clone()));
+                c.request_with_headers("cors; preflight from an allowed origin", Method::OPTIONS, preflight, None, Some(StatusCode::NO_CONTENT)).await;
+                check_allowed(c, origin);
+
+                let allow_methods = c.response_header_list("access-control-allow-methods");
+                c.check(
+                    allow_methods.iter().any(|allowed| allowed == method.as_str()),
+                    format!("access-control-allow-methods '{}' does not include {}", allow_methods.join(", "), method),
+                );
+                let allow_headers: Vec<String> = c.response_header_list("access-control-allow-headers").iter().map(|header| header.to_lowercase()).collect();
+                for request_header in &request_headers {
+                    c.check(
+                        allow_headers.iter().any(|header| header == request_header),
+                        format!("access-control-allow-headers '{}' does not include {}", allow_headers.join(", "), request_header),
+                    );
+                }
+            }
+
+            let mut preflight = headers.clone();
+            preflight.push(("origin", DISALLOWED_ORIGIN.into()));
+            c.request_with_headers("cors; preflight from a disallowed origin", Method::OPTIONS, preflight, None, None).await;
+            check_disallowed(c);
+        }
+
+        // GET is safe to repeat, so actual requests are only checked with it
+        if operations.get("get").is_some() {
+            c.expect_json = operations["get"]["responses"]["200"].get("content").is_some();
+            for origin in &origins {
+                let headers = vec![("cookie", format!("token={}", secret)), ("origin", origin.clone())];
+                c.request_with_headers("cors; request from an allowed origin", Method::GET, headers, None, Some(StatusCode::OK)).await;
+                check_allowed(c, origin);
+            }
+            let headers = vec![("cookie", format!("token={}", secret)), ("origin", DISALLOWED_ORIGIN.into())];
+            c.request_with_headers("cors; request from a disallowed origin", Method::GET, headers, None, None).await;
+            check_disallowed(c);
+            c.expect_json = true;
+        }
+    }
+}
+
+/// The non-simple headers a browser would ask to send: content-type for a
+/// json body, and for an operation that needs a token, those the selected
+/// profiles add.
+fn request_headers(c: &crate::checker::Checker, method: &Method, operations: &serde_json::Value) -> Vec<&'static str> {
+    let operation = &operations[method.as_str().to_lowercase()];
+    let mut headers = vec![];
+    if operation.get("requestBody").is_some() {
+        headers.push("content-type");
+    }
+    if operation.get("security").is_none() {
+        return headers
+    }
+    if c.config.profiles.contains(&Profile::Bearer) {
+        headers.push("authorization");
+    }
+    let safe = *method == Method::GET || *method == Method::HEAD || *method == Method::OPTIONS;
+    if !safe && c.config.profiles.contains(&Profile::Csrf) {
+        match c.config.csrf {
+            Csrf::Origin => {}
+            Csrf::Header => headers.push("x-requested-with"),
+            Csrf::DoubleSubmit => headers.push("x-csrf-token"),
+        }
+    }
+    headers
+}
+
+/// Credentials need the origin echoed back rather than `*`, and a response
+/// that varies by origin must say so for caches.
+fn check_allowed(c: &mut crate::checker::Checker, origin: &str) {
+    let allow_origin = header(c, "access-control-allow-origin");
+    c.check(
+        allow_origin.as_deref() == Some(origin),
+        format!("access-control-allow-origin is {:?} instead of '{}'", allow_origin, origin),
+    );
+    let allow_credentials = header(c, "access-control-allow-credentials");
+    c.check(
+        allow_credentials.as_deref() == Some("true"),
+        format!("access-control-allow-credentials is {:?} instead of 'true'", allow_credentials),
+    );
+    let vary: Vec<String> = c.response_header_list("vary").iter().map(|name| name.to_lowercase()).collect();
+    c.check(
+        vary.iter().any(|name| name == "origin" || name == "*"),
+        format!("vary '{}' does not include origin", vary.join(", ")),
+    );
+}
+
+fn check_disallowed(c: &mut crate::checker::Checker) {
+    let cors_headers: Vec<String> = c.response_headers.keys()
+        .map(|name| name.as_str().to_string())
+        .filter(|name| name.starts_with("access-control-allow-"))
+        .collect();
+    c.check(
+        cors_headers.is_empty(),
+        format!("response to a disallowed origin has {}", cors_headers.join(", ")),
+    );
+}
+
+fn header(c: &crate::checker::Checker, name: &str) -> Option<String> {
+    c.response_headers.get(name).map(|value| value.to_str().unwrap_or("").to_string())
+}
+
@@ -0,0 +1,104 @@
+use hyper::{Method, StatusCode};
+
+use crate::config::Csrf;
+use crate::spec::Version;
+
+pub const COVERS: &[&str] = &[
+    "csrf.refresh",
+    "csrf.delete-id",
+    "csrf.delete-current",
+    "csrf.password-change",
+    "csrf.delete-user",
+    "csrf.same-site",
+    "csrf.double-submit-cookie",
+];
+
+/// A site other than the server's.
+const CROSS_SITE_ORIGIN: &str = "https://cross-site.example";
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    let (email_1, tokens) = match c.log_in("csrf; log in", 2).await {
+        Some(identity) => (identity.email, identity.tokens),
+        None => return,
+    };
+    if c.config.csrf == Csrf::DoubleSubmit {
+        let csrf_cookie = c.response_headers.get_all("set-cookie").iter()
+            .any(|cookie| cookie.to_str().unwrap_or("").trim_start().starts_with("csrf="));
+        c.check(csrf_cookie, "login did not set a csrf cookie".into());
+    }
+    let secret = tokens[0].1.clone();
+
+    // each is tried cross-site first, so the same-site request also shows
+    // the cross-site one had no effect
+    c.path = "/tokens/current/refresh".into();
+    check_cross_site(c, Method::POST, &secret, None).await;
+    c.request_with_token("csrf; same-site refresh", Method::POST, secret.clone(), StatusCode::OK).await;
+
+    c.path = format!("/tokens/{}", tokens[1].0);
+    check_cross_site(c, Method::DELETE, &secret, None).await;
+    c.request_with_token("csrf; same-site delete token", Method::DELETE, secret.clone(), StatusCode::OK).await;
+
+    if c.config.spec_version >= Version::V3 {
+        let body = serde_json::json!({ "current_password": c.password(), "new_password": c.other_password(2) }).to_string();
+        c.path = "/users/current/password".into();
+        check_cross_site(c, Method::PUT, &secret, Some(body.clone())).await;
+        c.request_with_token_body("csrf; same-site password change", Method::PUT, secret.clone(), Some("application/json"), body, StatusCode::OK).await;
+    }
+
+    c.path = "/tokens/current".into();
+    check_cross_site(c, Method::DELETE, &secret, None).await;
+    c.request_with_token("csrf; same-site log out", Method::DELETE, secret, StatusCode::OK).await;
+
+    if c.config.spec_version >= Version::V3 {
+        let (json_response, _) = c.create_token("csrf; log in again", &email_1, &c.other_password(2), StatusCode::OK).await;
+        let secret = match json_response.and_then(|json| Some(json["secret"].as_str()?.to_string())) {
+            Some(secret) => secret,
+            None => {
+                c.fail("could not log in again to check deleting the identity".into());
+                return
+            }
+        };
+        c.path = "/users/current".into();
+        check_cross_site(c, Method::DELETE, &secret, None).await;
+        c.request_with_token("csrf; same-site delete identity", Method::DELETE, secret, StatusCode::OK).await;
+    }
+}
+
+/// Sends `method` as a cross-site page could for the configured protection,
+/// each expected to be rejected. A `body` is sent as json.
+async fn check_cross_site(c: &mut crate::checker::Checker, method: Method, secret: &str, body: Option<String>) {
+    let mut same_site = c.token_headers(&method, secret);
+    if body.is_some() {
+        same_site.push(("content-type", "application/json".into()));
+    }
+    let cookie_only: Vec<(&'static str, String)> = same_site.iter().filter(|(name, _)| matches!(*name, "cookie" | "content-type")).cloned().collect();
+
+    let mut attempts = vec![];
+    match c.config.csrf {
+        Csrf::Origin => {
+            attempts.push(("csrf; cross-site origin", with(&cookie_only, ("origin", CROSS_SITE_ORIGIN.into()))));
+            attempts.push(("csrf; opaque origin", with(&cookie_only, ("origin", "null".into()))));
+            attempts.push(("csrf; cross-site referer", with(&cookie_only, ("referer", format!("{}/page", CROSS_SITE_ORIGIN)))));
+        }
+        Csrf::Header => {
+            attempts.push(("csrf; no x-requested-with", with(&cookie_only, ("origin", CROSS_SITE_ORIGIN.into()))));
+        }
+        Csrf::DoubleSubmit => {
+            attempts.push(("csrf; no x-csrf-token", same_site.iter().filter(|(name, _)| *name != "x-csrf-token").cloned().collect()));
+            attempts.push(("csrf; wrong x-csrf-token", same_site.iter()
+                .map(|(name, value)| if *name == "x-csrf-token" { (*name, format!("{}x", value)) } else { (*name, value.clone()) })
+                .collect()));
+        }
+    }
+
+    for (group, headers) in attempts {
+        let response = c.request_with_headers(group, method.clone(), headers, body.clone(), Some(StatusCode::FORBIDDEN)).await;
+        c.check_error_response_multi(response, "csrf_rejected", vec!["csrf", "cross-site", "origin", "forbidden"]);
+    }
+}
+
+fn with(headers: &[(&'static str, String)], header: (&'static str, String)) -> Vec<(&'static str, String)> {
+    let mut headers = headers.to_vec();
+    headers.push(header);
+    headers
+}
@@ -0,0 +1,86 @@
+use hyper::StatusCode;
+
+pub const COVERS: &[&str] = &[
+    "emails.no-at",
+    "emails.empty-local",
+    "emails.empty-domain",
+    "emails.inner-whitespace",
+    "emails.trimmed",
+    "emails.trimmed-login",
+    "emails.case-unique",
+    "emails.case-login",
+];
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    let suffix = format!("{:0>8x}", rand::random::<u32>());
+
+    // error cases
+    c.path = "/users".into();
+    let invalid = [
+        ("email syntax; no @", format!("test+{}.example.com", suffix)),
+        ("email syntax; nothing before @", "@example.com".to_string()),
+        ("email syntax; nothing after @", format!("test+{}@", suffix)),
+        ("email syntax; whitespace inside", format!("test {}@example.com", suffix)),
+    ];
+    for (group, email) in invalid.iter() {
+        let response = c.post(group, format!(r#"{{"email":"{}","password":"{}"}}"#, email, c.password()), StatusCode::BAD_REQUEST).await;
+        c.check_error_response(response, "email_invalid", "email");
+    }
+
+    // surrounding whitespace is trimmed
+    let email_1 = format!("test+{}@example.com", suffix);
+    let (json_response, _) = c.post(
+        "email whitespace; register with surrounding whitespace",
+        format!(r#"{{"email":" {}\t","password":"{}"}}"#, email_1, c.password()),
+        StatusCode::OK,
+    ).await;
+    if let Some(json_response) = json_response {
+        if let Some(email) = c.get_property_string(&json_response, "email") {
+            c.check(
+                email == email_1,
+                format!("expected email to be trimmed to '{}' but got '{}'", email_1, email)
+            );
+        }
+    } else {
+        c.fail("response is not json".into());
+    }
+    c.path = "/tokens".into();
+    c.post(
+        "email whitespace; log in with surrounding whitespace",
+        format!(r#"{{"email":"  {} ", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, c.password()),
+        StatusCode::OK,
+    ).await;
+
+    // case-insensitive: a case variant is the same identity
+    let email_2 = format!("Test+{}@Example.COM", suffix);
+    c.path = "/users".into();
+    if c.config.strict_enumeration {
+        // duplicates look like a success, so only the login below shows
+        // nothing was created
+        c.post(
+            "email case; register case variant",
+            format!(r#"{{"email":"{}","password":"{}"}}"#, email_2, c.other_password(2)),
+            StatusCode::OK,
+        ).await;
+    } else {
+        let response = c.post(
+            "email case; register case variant",
+            format!(r#"{{"email":"{}","password":"{}"}}"#, email_2, c.other_password(2)),
+            StatusCode::BAD_REQUEST,
+        ).await;
+        c.check_error_response(response, "email_in_use", "in use");
+    }
+
+    c.path = "/tokens".into();
+    let response = c.post(
+        "email case; case variant's password rejected",
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_2, c.other_password(2)),
+        StatusCode::BAD_REQUEST,
+    ).await;
+    c.check_error_response(response, "credentials_invalid", "invalid");
+    c.post(
+        "email case; log in with case variant",
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_2, c.password()),
+        StatusCode::OK,
+    ).await;
+}
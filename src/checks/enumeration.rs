@@ -0,0 +1,128 @@
+use hyper::{HeaderMap, StatusCode};
+
+use crate::har::Exchange;
+
+/// Headers expected to differ between any two responses.
+const VOLATILE_HEADERS: &[&str] = &["date", "x-request-id"];
+
+pub const COVERS: &[&str] = &[
+    "enumeration.login",
+    "enumeration.registration-shape",
+    "enumeration.registration-message",
+];
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    let email_1 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
+    let email_2 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
+
+    c.path = "/users".into();
+    c.post(
+        "login enumeration; create",
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, c.password()),
+        StatusCode::OK,
+    ).await;
+
+    c.path = "/tokens".into();
+    c.post(
+        "login enumeration; unknown email",
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_2, c.password()),
+        StatusCode::BAD_REQUEST,
+    ).await;
+    let unknown_email = last_exchange(c);
+    c.post(
+        "login enumeration; wrong password",
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, c.other_password(1)),
+        StatusCode::BAD_REQUEST,
+    ).await;
+    let wrong_password = last_exchange(c);
+
+    check_status(c, &unknown_email, &wrong_password, "unknown email", "wrong password");
+    check_headers(c, &unknown_email, &wrong_password, "unknown email", "wrong password");
+    c.check(
+        unknown_email.response_body == wrong_password.response_body,
+        format!(
+            "unknown email and wrong password bodies differ: '{}' vs '{}'",
+            String::from_utf8_lossy(&unknown_email.response_body), String::from_utf8_lossy(&wrong_password.response_body),
+        ),
+    );
+
+    if !c.config.strict_enumeration {
+        return
+    }
+
+    c.path = "/users".into();
+    let (new_json, _) = c.post(
+        "registration enumeration; new email",
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_2, c.password()),
+        StatusCode::OK,
+    ).await;
+    let new_email = last_exchange(c);
+    let (duplicate_json, _) = c.post(
+        "registration enumeration; duplicate email",
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, c.password()),
+        StatusCode::OK,
+    ).await;
+    let duplicate_email = last_exchange(c);
+
+    // ids and emails make the bodies differ, so only the shape is compared
+    check_status(c, &new_email, &duplicate_email, "new email", "duplicate email");
+    let header_names = |exchange: &Exchange| -> Vec<String> {
+        let mut names: Vec<String> = exchange.response_headers.keys().map(|name| name.to_string()).collect();
+        names.sort();
+        names.dedup();
+        names
+    };
+    let (new_headers, duplicate_headers) = (header_names(&new_email), header_names(&duplicate_email));
+    c.check(
+        new_headers == duplicate_headers,
+        format!("new email has headers {:?} but duplicate email has {:?}", new_headers, duplicate_headers),
+    );
+    let property_names = |json: &Option<serde_json::Value>| -> Vec<String> {
+        let mut names: Vec<String> = json
+            .as_ref()
+            .and_then(|json| json.as_object())
+            .map(|object| object.keys().cloned().collect())
+            .unwrap_or_default();
+        names.sort();
+        names
+    };
+    let (new_names, duplicate_names) = (property_names(&new_json), property_names(&duplicate_json));
+    c.check(
+        new_names == duplicate_names,
+        format!("new email has properties {:?} but duplicate email has {:?}", new_names, duplicate_names),
+    );
+    let duplicate_body = String::from_utf8_lossy(&duplicate_email.response_body).to_lowercase();
+    c.check(
+        !["in use", "exists", "already", "taken", "duplicate"].iter().any(|needle| duplicate_body.contains(needle)),
+        format!("duplicate email response reveals the email is registered: '{}'", duplicate_body),
+    );
+}
+
+/// The request just sent and its response.
+fn last_exchange(c: &crate::checker::Checker) -> Exchange {
+    c.exchanges.last().cloned().expect("a request was sent")
+}
+
+fn check_status(c: &mut crate::checker::Checker, a: &Exchange, b: &Exchange, a_name: &str, b_name: &str) {
+    c.check(
+        a.status == b.status,
+        format!("{} returned '{}' but {} returned '{}'", a_name, a.status, b_name, b.status),
+    );
+}
+
+fn check_headers(c: &mut crate::checker::Checker, a: &Exchange, b: &Exchange, a_name: &str, b_name: &str) {
+    let stable = |headers: &HeaderMap| -> Vec<(String, String)> {
+        let mut stable: Vec<(String, String)> = headers
+            .iter()
+            .filter(|(name, _)| !VOLATILE_HEADERS.contains(&name.as_str()))
+            .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).to_string()))
+            .collect();
+        stable.sort();
+        stable
+    };
+    let (a_headers, b_headers) = (stable(&a.response_headers), stable(&b.response_headers));
+    c.check(
+        a_headers == b_headers,
+        format!("{} and {} headers differ: {:?} vs {:?}", a_name, b_name, a_headers, b_headers),
+    );
+}
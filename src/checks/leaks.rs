@@ -0,0 +1,83 @@
+use hyper::StatusCode;
+
+use crate::spec::Profile;
+
+pub const COVERS: &[&str] = &[
+    "leak.password",
+    "leak.secret-body",
+    "leak.secret-header",
+    "leak.password-hash",
+];
+
+/// Walks every endpoint with the random test password so the leak detector in
+/// `Checker::check_leaks` has something to look for. The detector itself runs
+/// on every response of every check.
+pub async fn check(c: &mut crate::checker::Checker) {
+    let email_1 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
+    let password_1 = c.password();
+
+    c.path = "/users".into();
+    c.post(
+        "never leak; create",
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, password_1),
+        StatusCode::OK,
+    ).await;
+    // with --strict-enumeration a duplicate looks like a success
+    let duplicate_status = if c.config.strict_enumeration { StatusCode::OK } else { StatusCode::BAD_REQUEST };
+    c.post(
+        "never leak; duplicate",
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, password_1),
+        duplicate_status,
+    ).await;
+
+    let db_inspection = c.config.profiles.contains(&Profile::DbInspection);
+    if let Some(db) = c.db.as_ref().filter(|_| db_inspection) {
+        let stored = db.query_value(
+            "SELECT password FROM identity WHERE lower(email) = lower(:'email');",
+            &[("email", &email_1)],
+        ).await;
+        match stored {
+            Ok(Some(stored)) => c.remember_sensitive(stored, "password hash"),
+            Ok(None) => c.fail(format!("no identity row found for '{}'", email_1)),
+            Err(e) => c.fail(e),
+        }
+    }
+
+    c.path = "/tokens".into();
+    c.post(
+        "never leak; wrong password",
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, c.other_password(1)),
+        StatusCode::BAD_REQUEST,
+    ).await;
+    let mut secrets = vec![];
+    for _ in 0..2 {
+        let (json_response, _) = c.post(
+            "never leak; create token",
+            format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, password_1),
+            StatusCode::OK,
+        ).await;
+        if let Some(json_response) = json_response {
+            if let Some(secret) = c.get_property_string(&json_response, "secret") {
+                secrets.push(secret);
+            }
+        } else {
+            c.fail("token create response was not json".into());
+        }
+    }
+    if secrets.len() != 2 {
+        return
+    }
+    let secret = secrets.remove(0);
+
+    c.path = "/tokens".into();
+    c.get_with_token("never leak; list tokens", secret.clone(), StatusCode::OK).await;
+
+    c.path = "/tokens/current".into();
+    c.get_with_token("never leak; current token", secret.clone(), StatusCode::OK).await;
+
+    c.path = "/tokens/current/refresh".into();
+    c.post_with_token("never leak; refresh", secret.clone(), StatusCode::OK).await;
+
+    c.path = "/tokens/current".into();
+    c.delete_with_token("never leak; delete current token", secret, StatusCode::OK).await;
+}
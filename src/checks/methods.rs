@@ -0,0 +1,94 @@
+use hyper::{Method, StatusCode};
+
+pub const COVERS: &[&str] = &[
+    "methods.405-allow",
+    "methods.unlisted",
+    "methods.head",
+    "methods.options",
+];
+
+/// Methods every route is tried with; any the spec doesn't list must be 405.
+const METHODS: &[Method] = &[
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::PATCH,
+    Method::DELETE,
+    Method::TRACE,
+];
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    // a valid token, so routes that need one get as far as method handling
+    let (token_id, secret) = match c.log_in("methods; log in", 1).await {
+        Some(identity) => identity.tokens[0].clone(),
+        None => return,
+    };
+
+    let document = crate::openapi::document();
+    for (template, operations) in document["paths"].as_object().into_iter().flatten() {
+        // the capability document is optional and may be a static file, and
+        // paths from later spec versions don't exist yet
+        if template.starts_with("/.well-known/") || crate::openapi::since(operations) > c.config.spec_version {
+            continue
+        }
+        let listed = crate::openapi::methods(operations);
+        c.path = template.replace("{id}", &token_id);
+
+        c.request_with_token("methods; OPTIONS lists allowed methods", Method::OPTIONS, secret.clone(), StatusCode::NO_CONTENT).await;
+        check_allow(c, &listed);
+
+        for method in METHODS.iter().filter(|method| !listed.contains(method)) {
+            let response = c.request_with_token(
+                "methods; unlisted method",
+                method.clone(),
+                secret.clone(),
+                StatusCode::METHOD_NOT_ALLOWED,
+            ).await;
+            c.check_error_response(response, "method_not_allowed", "method");
+            check_allow(c, &listed);
+        }
+
+        if listed.contains(&Method::GET) {
+            c.expect_json = operations["get"]["responses"]["200"].get("content").is_some();
+            c.request_with_token("methods; HEAD mirrors GET", Method::GET, secret.clone(), StatusCode::OK).await;
+            let get_content_type = c.response_headers.get("content-type").cloned();
+            let get_length = c.response_body.len();
+            let get_has_length = c.response_headers.contains_key("content-length");
+            c.request_with_token("methods; HEAD mirrors GET", Method::HEAD, secret.clone(), StatusCode::OK).await;
+            // the client never reads a HEAD body, so the length GET would
+            // send is all there is to compare
+            let length = c.response_headers.get("content-length")
+                .map(|length| length.to_str().ok().and_then(|length| length.parse::<usize>().ok()));
+            c.check(
+                length.map_or(!get_has_length, |length| length == Some(get_length)),
+                format!("HEAD content-length is {:?} but GET's body is {} bytes", length.flatten(), get_length),
+            );
+            let content_type = c.response_headers.get("content-type").cloned();
+            c.check(
+                content_type == get_content_type,
+                format!("HEAD content-type is {:?} but GET's is {:?}", content_type, get_content_type),
+            );
+            c.expect_json = true;
+        }
+    }
+}
+
+/// The allow header must list every method the spec does, and may add HEAD
+/// (when GET is listed) and OPTIONS.
+fn check_allow(c: &mut crate::checker::Checker, listed: &[Method]) {
+    let allow: Vec<String> = c.response_header_list("allow").iter().map(|method| method.to_uppercase()).collect();
+    if allow.is_empty() {
+        c.fail("missing allow header".into());
+        return
+    }
+
+    let missing: Vec<&str> = listed.iter().map(|method| method.as_str()).filter(|method| !allow.iter().any(|allowed| allowed == method)).collect();
+    c.check(missing.is_empty(), format!("allow header '{}' is missing {}", allow.join(", "), missing.join(", ")));
+
+    let extra: Vec<&str> = allow.iter()
+        .map(|method| method.as_str())
+        .filter(|method| !listed.iter().any(|listed| listed == method))
+        .filter(|method| !(*method == "OPTIONS" || (*method == "HEAD" && listed.contains(&Method::GET))))
+        .collect();
+    c.check(extra.is_empty(), format!("allow header '{}' lists {} which the spec doesn't", allow.join(", "), extra.join(", ")));
+}
@@ -0,0 +1,108 @@
+use hyper::{Method, StatusCode};
+
+pub const COVERS: &[&str] = &[
+    "users-current-password.put.no-token",
+    "users-current-password.put.no-body",
+    "users-current-password.put.content-type",
+    "users-current-password.put.parse",
+    "users-current-password.put.current-password-missing",
+    "users-current-password.put.current-password-string",
+    "users-current-password.put.new-password-missing",
+    "users-current-password.put.new-password-string",
+    "users-current-password.put.current-password-invalid",
+    "users-current-password.put.ok",
+    "users-current-password.put.other-tokens",
+    "users-current-password.put.keep-current-token",
+    "users-current-password.put.new-password-only",
+    "users-current-password.other.405",
+];
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    let (email_1, secrets) = match c.log_in("password change; log in", 3).await {
+        Some(identity) => (identity.email, identity.tokens.into_iter().map(|(_, secret)| secret).collect::<Vec<String>>()),
+        None => return,
+    };
+    let secret = secrets[0].clone();
+    let (password_1, password_2, password_3) = (c.password(), c.other_password(2), c.other_password(3));
+    let change = serde_json::json!({ "current_password": password_1, "new_password": password_2 }).to_string();
+
+    // error cases
+    c.path = "/users/current/password".into();
+    c.request_with_headers(
+        "password change; no token",
+        Method::PUT,
+        vec![("content-type", "application/json".into())],
+        Some(change.clone()),
+        Some(StatusCode::UNAUTHORIZED),
+    ).await;
+
+    let response = c.request_with_token("password change; no body/content-length", Method::PUT, secret.clone(), StatusCode::BAD_REQUEST).await;
+    c.check_error_response_multi(response, "body_missing", vec!["body", "content-length"]);
+
+    let response = c.request_with_token_body(
+        "password change; content-type other than null or application/json",
+        Method::PUT,
+        secret.clone(),
+        Some("image/png"),
+        change.clone(),
+        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+    ).await;
+    c.check_error_response_multi(response, "content_type_unsupported", vec!["content-type", "unsupported media type"]);
+
+    let invalid = [
+        ("password change; can't parse json", "not json".to_string(), "json_invalid", "parse"),
+        ("password change; missing current password", serde_json::json!({ "new_password": password_2 }).to_string(), "current_password_missing", "password"),
+        ("password change; current password must be string", serde_json::json!({ "current_password": 123, "new_password": password_2 }).to_string(), "current_password_not_string", "string"),
+        ("password change; missing new password", serde_json::json!({ "current_password": password_1 }).to_string(), "new_password_missing", "password"),
+        ("password change; new password must be string", serde_json::json!({ "current_password": password_1, "new_password": 123 }).to_string(), "new_password_not_string", "string"),
+        ("password change; wrong current password", serde_json::json!({ "current_password": c.other_password(1), "new_password": password_2 }).to_string(), "current_password_invalid", "password"),
+    ];
+    for (group, body, code, needle) in invalid.iter() {
+        let response = c.request_with_token_body(group, Method::PUT, secret.clone(), Some("application/json"), body.clone(), StatusCode::BAD_REQUEST).await;
+        c.check_error_response(response, code, needle);
+    }
+    // none of the errors may have changed anything
+    c.path = "/tokens/current".into();
+    c.get_with_token("password change; tokens kept after errors", secrets[1].clone(), StatusCode::OK).await;
+
+    c.path = "/users/current/password".into();
+    let response = c.request_with_token("password change; method not allowed", Method::GET, secret.clone(), StatusCode::METHOD_NOT_ALLOWED).await;
+    c.check_error_response(response, "method_not_allowed", "method");
+
+    // success cases
+    c.request_with_token_body(
+        "password change; correct response format",
+        Method::PUT,
+        secret.clone(),
+        None,
+        change.clone(),
+        StatusCode::OK,
+    ).await;
+
+    c.path = "/tokens/current".into();
+    c.get_with_token("password change; current token kept by default", secret.clone(), StatusCode::OK).await;
+    for other in &secrets[1..] {
+        c.get_with_token("password change; other tokens deleted", other.clone(), StatusCode::UNAUTHORIZED).await;
+    }
+
+    let response = c.create_token("password change; old password rejected", &email_1, &password_1, StatusCode::BAD_REQUEST).await;
+    c.check_error_response(response, "credentials_invalid", "invalid");
+    let (json_response, _) = c.create_token("password change; new password accepted", &email_1, &password_2, StatusCode::OK).await;
+    let other = json_response.and_then(|json| Some(json["secret"].as_str()?.to_string()));
+
+    c.path = "/users/current/password".into();
+    c.request_with_token_body(
+        "password change; keep_current_token false",
+        Method::PUT,
+        secret.clone(),
+        Some("application/json"),
+        serde_json::json!({ "current_password": password_2, "new_password": password_3, "keep_current_token": false }).to_string(),
+        StatusCode::OK,
+    ).await;
+
+    c.path = "/tokens/current".into();
+    c.get_with_token("password change; current token deleted without keep_current_token", secret, StatusCode::UNAUTHORIZED).await;
+    if let Some(other) = other {
+        c.get_with_token("password change; other tokens deleted", other, StatusCode::UNAUTHORIZED).await;
+    }
+}
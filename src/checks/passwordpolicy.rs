@@ -0,0 +1,137 @@
+use hyper::{Method, StatusCode};
+
+use crate::checker::new_email;
+
+pub const COVERS: &[&str] = &[
+    "password-policy.too-short",
+    "password-policy.min-length",
+    "password-policy.max-length",
+    "password-policy.too-long",
+    "password-policy.huge",
+    "password-policy.no-truncation",
+    "password-policy.nfc",
+    "password-policy.breached",
+    "password-policy.change",
+];
+
+/// Far past any sensible limit: hashing it, or bcrypt'ing anything derived
+/// from it without a length check, is a denial of service.
+const HUGE_PASSWORD_BYTES: usize = 1024 * 1024;
+
+/// How long rejecting a huge password may take, when a typical hash takes
+/// well under this.
+const HUGE_PASSWORD_MILLIS: u128 = 1000;
+
+/// 4 bytes in utf-8, so a few of them pass bcrypt's 72 byte limit even when
+/// the length limit is in the tens of code points.
+const WIDE_CHAR: char = '\u{1F511}';
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    let min_length = c.config.password_min_length as usize;
+    let max_length = c.config.password_max_length as usize;
+
+    // length limits
+    if min_length > 0 {
+        let response = c.register("password policy; one shorter than the minimum", &new_email(), &"p".repeat(min_length - 1), StatusCode::BAD_REQUEST).await;
+        c.check_error_response(response, "password_too_short", "short");
+    }
+    c.register("password policy; minimum length", &new_email(), &"p".repeat(min_length), StatusCode::OK).await;
+
+    let response = c.register("password policy; one longer than the maximum", &new_email(), &"p".repeat(max_length + 1), StatusCode::BAD_REQUEST).await;
+    c.check_error_response(response, "password_too_long", "long");
+    let email_1 = new_email();
+    let password_1 = format!("{}{}", WIDE_CHAR, "p".repeat(max_length.saturating_sub(1)));
+    c.register("password policy; maximum length", &email_1, &password_1, StatusCode::OK).await;
+    c.create_token("password policy; log in with maximum length", &email_1, &password_1, StatusCode::OK).await;
+
+    // rejected before any hashing, so quickly; no stored password is that
+    // long, so a login with one is just invalid
+    let huge = "p".repeat(HUGE_PASSWORD_BYTES);
+    let response = c.register("password policy; huge password", &new_email(), &huge, StatusCode::BAD_REQUEST).await;
+    c.check_error_response(response, "password_too_long", "long");
+    check_quick(c);
+    let response = c.create_token("password policy; log in with huge password", &email_1, &huge, StatusCode::BAD_REQUEST).await;
+    c.check_error_response(response, "credentials_invalid", "invalid");
+    check_quick(c);
+
+    // passwords differing only past bcrypt's 72 bytes are different passwords
+    let prefix = WIDE_CHAR.to_string().repeat(std::cmp::max(19, min_length.saturating_sub(1)));
+    if prefix.chars().count() < max_length {
+        let email_1 = new_email();
+        c.register("password policy; past 72 bytes; create", &email_1, &format!("{}1", prefix), StatusCode::OK).await;
+        let response = c.create_token(
+            "password policy; past 72 bytes; differs only after byte 72",
+            &email_1,
+            &format!("{}2", prefix),
+            StatusCode::BAD_REQUEST,
+        ).await;
+        c.check_error_response(response, "credentials_invalid", "invalid");
+    } else {
+        c.skip("password policy; past 72 bytes", "maximum length is too short to pass 72 bytes");
+    }
+
+    // NFC "caf\u{e9}" and NFD "cafe\u{301}" are the same password
+    let padding = "p".repeat(min_length.saturating_sub(4));
+    let nfc = format!("caf\u{e9}{}", padding);
+    let nfd = format!("cafe\u{301}{}", padding);
+    for (group, create, log_in) in [
+        ("password policy; create NFC, log in NFD", &nfc, &nfd),
+        ("password policy; create NFD, log in NFC", &nfd, &nfc),
+    ] {
+        let email_1 = new_email();
+        c.register(group, &email_1, create, StatusCode::OK).await;
+        c.create_token(group, &email_1, log_in, StatusCode::OK).await;
+    }
+
+    // breached passwords, from the same list the server uses
+    match c.config.breached_passwords.clone() {
+        Some(file) => match std::fs::read_to_string(&file) {
+            Ok(list) => {
+                let breached: Vec<&str> = list.lines()
+                    .map(|password| password.trim_end_matches('\r'))
+                    .filter(|password| (min_length..=max_length).contains(&password.chars().count()))
+                    .take(5)
+                    .collect();
+                if breached.is_empty() {
+                    c.fail(format!("{} has no passwords within the length limits", file));
+                }
+                for password in breached {
+                    let response = c.register("password policy; breached password", &new_email(), password, StatusCode::BAD_REQUEST).await;
+                    c.check_error_response(response, "password_breached", "breach");
+                }
+            }
+            Err(e) => c.fail(format!("could not read {}: {}", file, e)),
+        },
+        None => c.skip("password policy; breached password", "no --breached-passwords given"),
+    }
+
+    // the same rules for a changed password
+    let email_1 = new_email();
+    let password_1 = "p".repeat(min_length);
+    c.register("password policy; change; create", &email_1, &password_1, StatusCode::OK).await;
+    let (json_response, _) = c.create_token("password policy; change; log in", &email_1, &password_1, StatusCode::OK).await;
+    if let Some(secret) = json_response.and_then(|json| Some(json["secret"].as_str()?.to_string())) {
+        c.path = "/users/current/password".into();
+        let response = c.request_with_token_body(
+            "password policy; change to a password one longer than the maximum",
+            Method::PUT,
+            secret,
+            Some("application/json"),
+            serde_json::json!({ "current_password": password_1, "new_password": "p".repeat(max_length + 1) }).to_string(),
+            StatusCode::BAD_REQUEST,
+        ).await;
+        c.check_error_response(response, "password_too_long", "long");
+    } else {
+        c.fail("could not log in to check password change".into());
+    }
+}
+
+/// Fails if the last request took longer than a huge password should.
+fn check_quick(c: &mut crate::checker::Checker) {
+    if let Some(time) = c.exchanges.last().map(|exchange| exchange.time.as_millis()) {
+        c.check(
+            time < HUGE_PASSWORD_MILLIS,
+            format!("took {}ms to reject a {} byte password, more than {}ms", time, HUGE_PASSWORD_BYTES, HUGE_PASSWORD_MILLIS),
+        );
+    }
+}
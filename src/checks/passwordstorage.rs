@@ -0,0 +1,186 @@
+use hyper::StatusCode;
+
+use crate::config::PasswordHashPolicy;
+
+pub const COVERS: &[&str] = &[
+    "password-storage.not-weak",
+    "password-storage.work-factor",
+];
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    if c.db.is_none() {
+        c.skip("password storage", "no --db-url given");
+        return
+    }
+
+    let email_1 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
+    let password_1 = c.password();
+
+    c.path = "/users".into();
+    c.post(
+        "password storage; create",
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, password_1),
+        StatusCode::OK,
+    ).await;
+
+    let stored = c.db.as_ref().unwrap().query_value(
+        "SELECT password FROM identity WHERE lower(email) = lower(:'email');",
+        &[("email", &email_1)],
+    ).await;
+    let stored = match stored {
+        Ok(Some(stored)) => stored,
+        Ok(None) => {
+            c.fail(format!("no identity row found for '{}'", email_1));
+            return
+        }
+        Err(e) => {
+            c.fail(e);
+            return
+        }
+    };
+
+    let hash = PasswordHash::classify(&stored, &password_1);
+    if let Err(problem) = hash.meets(&c.config.password_hash_policy) {
+        c.fail(format!("stored password {}", problem));
+    } else {
+        c.pass(1);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum PasswordHash {
+    Argon2id { memory_kib: u32 },
+    Argon2 { variant: String },
+    Scrypt { log_n: u32 },
+    Bcrypt { cost: u32 },
+    Pbkdf2 { iterations: u32 },
+    Plaintext,
+    Fast { algorithm: &'static str },
+    Unknown,
+}
+
+impl PasswordHash {
+    fn classify(stored: &str, password: &str) -> PasswordHash {
+        if stored == password {
+            return PasswordHash::Plaintext
+        }
+
+        let parts: Vec<&str> = stored.split('$').collect();
+        match parts.as_slice() {
+            // $argon2id$v=19$m=65536,t=3,p=4$salt$hash
+            ["", "argon2id", _, params, ..] => match param(params, "m") {
+                Some(memory_kib) => PasswordHash::Argon2id { memory_kib },
+                None => PasswordHash::Unknown,
+            },
+            ["", variant, ..] if variant.starts_with("argon2") && *variant != "argon2id" => {
+                PasswordHash::Argon2 { variant: variant.to_string() }
+            }
+            // $scrypt$ln=17,r=8,p=1$salt$hash
+            ["", "scrypt", params, ..] => match param(params, "ln") {
+                Some(log_n) => PasswordHash::Scrypt { log_n },
+                None => PasswordHash::Unknown,
+            },
+            // $2b$12$saltandhash
+            ["", "2a", cost, _] | ["", "2b", cost, _] | ["", "2y", cost, _] => match cost.parse() {
+                Ok(cost) => PasswordHash::Bcrypt { cost },
+                Err(_) => PasswordHash::Unknown,
+            },
+            // $pbkdf2-sha256$29000$salt$hash (passlib)
+            ["", algorithm, iterations, ..] if algorithm.starts_with("pbkdf2") => match iterations.parse() {
+                Ok(iterations) => PasswordHash::Pbkdf2 { iterations },
+                Err(_) => PasswordHash::Unknown,
+            },
+            // pbkdf2_sha256$260000$salt$hash (django)
+            [algorithm, iterations, ..] if algorithm.starts_with("pbkdf2") => match iterations.parse() {
+                Ok(iterations) => PasswordHash::Pbkdf2 { iterations },
+                Err(_) => PasswordHash::Unknown,
+            },
+            _ => PasswordHash::classify_fast(stored),
+        }
+    }
+
+    /// Bare hex digests, optionally with a salt on either side of a ':', are
+    /// recognised by length since that's all there is to go on.
+    fn classify_fast(stored: &str) -> PasswordHash {
+        let is_hex = |s: &str| !s.is_empty() && s.chars().all(|ch| ch.is_ascii_hexdigit());
+        let digest = stored.split(':').find(|part| is_hex(part) && [32, 40, 64, 128].contains(&part.len()));
+
+        match digest.map(|digest| digest.len()) {
+            Some(32) => PasswordHash::Fast { algorithm: "md5" },
+            Some(40) => PasswordHash::Fast { algorithm: "sha1" },
+            Some(64) => PasswordHash::Fast { algorithm: "sha256" },
+            Some(128) => PasswordHash::Fast { algorithm: "sha512" },
+            _ => PasswordHash::Unknown,
+        }
+    }
+
+    fn meets(&self, policy: &PasswordHashPolicy) -> Result<(), String> {
+        match self {
+            PasswordHash::Argon2id { memory_kib } => minimum("argon2id memory (KiB)", *memory_kib, policy.argon2_memory_kib),
+            PasswordHash::Argon2 { variant } => Err(format!("uses {} instead of argon2id", variant)),
+            PasswordHash::Scrypt { log_n } => minimum("scrypt log2(N)", *log_n, policy.scrypt_log_n),
+            PasswordHash::Bcrypt { cost } => minimum("bcrypt cost", *cost, policy.bcrypt_cost),
+            PasswordHash::Pbkdf2 { iterations } => minimum("pbkdf2 iterations", *iterations, policy.pbkdf2_iterations),
+            PasswordHash::Plaintext => Err("is plaintext".into()),
+            PasswordHash::Fast { algorithm } => Err(format!("looks like a fast {} hash", algorithm)),
+            PasswordHash::Unknown => Err("is in an unrecognized format".into()),
+        }
+    }
+}
+
+fn param(params: &str, name: &str) -> Option<u32> {
+    params
+        .split(',')
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+fn minimum(name: &str, actual: u32, required: u32) -> Result<(), String> {
+    if actual >= required {
+        Ok(())
+    } else {
+        Err(format!("{} is {}, minimum is {}", name, actual, required))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PasswordHash;
+
+    #[test]
+    fn classify_recognises_slow_hashes() {
+        assert_eq!(
+            PasswordHash::classify("$argon2id$v=19$m=65536,t=3,p=4$c2FsdA$aGFzaA", "pw"),
+            PasswordHash::Argon2id { memory_kib: 65536 },
+        );
+        assert_eq!(
+            PasswordHash::classify("$argon2i$v=19$m=65536,t=3,p=4$c2FsdA$aGFzaA", "pw"),
+            PasswordHash::Argon2 { variant: "argon2i".into() },
+        );
+        assert_eq!(PasswordHash::classify("$scrypt$ln=17,r=8,p=1$c2FsdA$aGFzaA", "pw"), PasswordHash::Scrypt { log_n: 17 });
+        assert_eq!(
+            PasswordHash::classify("$2b$12$R9h/cIPz0gi.URNNX3kh2OPST9/PgBkqquzi.Ss7KIUgO2t0jWMUW", "pw"),
+            PasswordHash::Bcrypt { cost: 12 },
+        );
+        assert_eq!(PasswordHash::classify("$pbkdf2-sha256$29000$c2FsdA$aGFzaA", "pw"), PasswordHash::Pbkdf2 { iterations: 29000 });
+        assert_eq!(PasswordHash::classify("pbkdf2_sha256$260000$salt$aGFzaA=", "pw"), PasswordHash::Pbkdf2 { iterations: 260000 });
+    }
+
+    #[test]
+    fn classify_recognises_weak_storage() {
+        assert_eq!(PasswordHash::classify("pw", "pw"), PasswordHash::Plaintext);
+        assert_eq!(PasswordHash::classify(&"a".repeat(32), "pw"), PasswordHash::Fast { algorithm: "md5" });
+        assert_eq!(PasswordHash::classify(&format!("salt:{}", "b".repeat(40)), "pw"), PasswordHash::Fast { algorithm: "sha1" });
+        assert_eq!(PasswordHash::classify(&"c".repeat(64), "pw"), PasswordHash::Fast { algorithm: "sha256" });
+        assert_eq!(PasswordHash::classify(&"d".repeat(128), "pw"), PasswordHash::Fast { algorithm: "sha512" });
+    }
+
+    #[test]
+    fn classify_rejects_malformed_hashes() {
+        assert_eq!(PasswordHash::classify("$argon2id$v=19$t=3,p=4$c2FsdA$aGFzaA", "pw"), PasswordHash::Unknown);
+        assert_eq!(PasswordHash::classify("$2b$x$R9h/cIPz0gi.URNNX3kh2OPST9/PgBkqquzi.Ss7KIUgO2t0jWMUW", "pw"), PasswordHash::Unknown);
+        assert_eq!(PasswordHash::classify(&"e".repeat(31), "pw"), PasswordHash::Unknown);
+        assert_eq!(PasswordHash::classify("", "pw"), PasswordHash::Unknown);
+    }
+}
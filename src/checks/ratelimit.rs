@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use hyper::StatusCode;
+
+pub const COVERS: &[&str] = &[
+    "rate-limit.under-limit",
+    "rate-limit.over-limit",
+    "rate-limit.retry-after",
+    "rate-limit.throttled-correct-password",
+    "rate-limit.other-identity",
+    "rate-limit.recovers",
+];
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    let (attempts, window_seconds) = match &c.config.rate_limit {
+        Some(rate_limit) => (rate_limit.attempts, rate_limit.window_seconds),
+        None => {
+            c.skip("rate limiting", "no --rate-limit-attempts and no rate_limit in the capability document");
+            return
+        }
+    };
+
+    let email_1 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
+    let email_2 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
+
+    c.path = "/users".into();
+    for email in &[&email_1, &email_2] {
+        c.post(
+            "rate limiting; create",
+            format!(r#"{{"email":"{}","password":"{}"}}"#, email, c.password()),
+            StatusCode::OK,
+        ).await;
+    }
+
+    c.path = "/tokens".into();
+    for _ in 0..attempts {
+        c.post(
+            "rate limiting; failed attempts under the limit",
+            format!(r#"{{"email":"{}", "password": "wrong", "lifetime": "no-expiration" }}"#, email_1),
+            StatusCode::BAD_REQUEST,
+        ).await;
+    }
+
+    let response = c.post(
+        "rate limiting; failed attempt over the limit",
+        format!(r#"{{"email":"{}", "password": "wrong", "lifetime": "no-expiration" }}"#, email_1),
+        StatusCode::TOO_MANY_REQUESTS,
+    ).await;
+    c.check_error_response_multi(response, "rate_limited", vec!["too many", "rate", "attempts"]);
+
+    let retry_after = c.response_headers
+        .get("retry-after")
+        .map(|value| value.to_str().unwrap_or("").to_string());
+    let retry_after_seconds = match retry_after {
+        None => {
+            c.fail("429 response has no retry-after header".into());
+            None
+        }
+        Some(retry_after) => match retry_after.parse::<u32>() {
+            Ok(seconds) => {
+                c.check(
+                    seconds <= window_seconds,
+                    format!("retry-after is {}s but the window is {}s", seconds, window_seconds),
+                );
+                Some(seconds)
+            }
+            Err(_) => {
+                c.fail(format!("retry-after is not a number of seconds: '{}'", retry_after));
+                None
+            }
+        },
+    };
+
+    c.post(
+        "rate limiting; correct password while throttled",
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, c.password()),
+        StatusCode::TOO_MANY_REQUESTS,
+    ).await;
+
+    c.post(
+        "rate limiting; other identity unaffected",
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_2, c.password()),
+        StatusCode::OK,
+    ).await;
+
+    let wait_seconds = retry_after_seconds.unwrap_or(window_seconds).min(window_seconds) + 1;
+    println!("Waiting {}s for the rate limit window to pass", wait_seconds);
+    tokio::time::delay_for(Duration::from_secs(wait_seconds as u64)).await;
+
+    c.post(
+        "rate limiting; recovers after the window",
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, c.password()),
+        StatusCode::OK,
+    ).await;
+}
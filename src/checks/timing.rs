@@ -0,0 +1,110 @@
+use hyper::StatusCode;
+
+/// |z| above this is treated as a real difference, roughly p < 0.001.
+const Z_THRESHOLD: f64 = 3.29;
+/// Differences smaller than this fraction of the median are ignored, so a
+/// large sample doesn't flag a few microseconds of noise.
+const MIN_RELATIVE_DIFFERENCE: f64 = 0.2;
+
+pub const COVERS: &[&str] = &[
+    "timing.login",
+];
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    // stay under the login throttle by switching identities before it kicks in
+    let attempts_per_identity = c.config.rate_limit.as_ref().map_or(u32::MAX, |rate_limit| rate_limit.attempts.max(1));
+    let mut known_email = String::new();
+
+    // interleave so drift in server load affects both samples equally
+    let mut unknown_email = vec![];
+    let mut wrong_password = vec![];
+    for sample in 0..c.config.timing_samples {
+        if sample % attempts_per_identity == 0 {
+            known_email = format!("test+{:0>8x}@example.com", rand::random::<u32>());
+            c.path = "/users".into();
+            c.post(
+                "login timing; create",
+                format!(r#"{{"email":"{}","password":"{}"}}"#, known_email, c.password()),
+                StatusCode::OK,
+            ).await;
+        }
+
+        c.path = "/tokens".into();
+        let email = format!("test+{:0>8x}@example.com", rand::random::<u32>());
+        c.post(
+            "login timing; unknown email",
+            format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email, c.password()),
+            StatusCode::BAD_REQUEST,
+        ).await;
+        unknown_email.push(last_exchange_millis(c));
+
+        c.post(
+            "login timing; wrong password",
+            format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, known_email, c.other_password(1)),
+            StatusCode::BAD_REQUEST,
+        ).await;
+        wrong_password.push(last_exchange_millis(c));
+    }
+    if unknown_email.is_empty() {
+        return
+    }
+
+    let z = mann_whitney_z(&unknown_email, &wrong_password);
+    let unknown_median = median(&mut unknown_email);
+    let wrong_median = median(&mut wrong_password);
+    let relative_difference = (unknown_median - wrong_median).abs() / unknown_median.max(wrong_median);
+
+    c.check(
+        z.abs() < Z_THRESHOLD || relative_difference < MIN_RELATIVE_DIFFERENCE,
+        format!(
+            "login time reveals whether the email exists: unknown email median {:.2}ms, wrong password median {:.2}ms, z = {:.2} over {} samples each",
+            unknown_median, wrong_median, z, unknown_email.len(),
+        ),
+    );
+}
+
+/// Network time of the last request, without the checker's own work on the
+/// response (parsing, schema validation, leak scanning).
+fn last_exchange_millis(c: &crate::checker::Checker) -> f64 {
+    c.exchanges.last().map_or(0.0, |exchange| exchange.time.as_secs_f64() * 1000.0)
+}
+
+fn median(samples: &mut [f64]) -> f64 {
+    samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let middle = samples.len() / 2;
+    if samples.len() % 2 == 1 {
+        samples[middle]
+    } else {
+        (samples[middle - 1] + samples[middle]) / 2.0
+    }
+}
+
+/// Mann-Whitney U test using the normal approximation, returned as a z score.
+/// Latencies are skewed with long tails, so a rank test is used rather than
+/// comparing means.
+fn mann_whitney_z(a: &[f64], b: &[f64]) -> f64 {
+    let mut all: Vec<(f64, bool)> = a.iter().map(|x| (*x, true)).chain(b.iter().map(|x| (*x, false))).collect();
+    all.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
+
+    // average ranks across ties
+    let mut rank_sum_a = 0.0;
+    let mut i = 0;
+    while i < all.len() {
+        let mut j = i;
+        while j + 1 < all.len() && all[j + 1].0 == all[i].0 {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        rank_sum_a += all[i..=j].iter().filter(|(_, from_a)| *from_a).count() as f64 * rank;
+        i = j + 1;
+    }
+
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let u = rank_sum_a - n_a * (n_a + 1.0) / 2.0;
+    let mean = n_a * n_b / 2.0;
+    let deviation = (n_a * n_b * (n_a + n_b + 1.0) / 12.0).sqrt();
+    if deviation == 0.0 {
+        return 0.0
+    }
+    (u - mean) / deviation
+}
@@ -1,104 +1,126 @@
 use hyper::StatusCode;
 
+pub const COVERS: &[&str] = &[
+    "tokens.post.no-body",
+    "tokens.post.content-type",
+    "tokens.post.parse",
+    "tokens.post.email-missing",
+    "tokens.post.email-string",
+    "tokens.post.email-too-long",
+    "tokens.post.password-missing",
+    "tokens.post.password-string",
+    "tokens.post.lifetime-missing",
+    "tokens.post.lifetime-string",
+    "tokens.post.lifetime-invalid",
+    "tokens.post.unknown-email",
+    "tokens.post.wrong-password",
+    "tokens.post.ok",
+    "tokens.post.ok-no-expiration",
+    "tokens.post.ok-remember-me",
+    "tokens.post.ok-until-idle",
+    "tokens.post.ok-json",
+    "tokens.post.ok-json-utf8",
+    "tokens.post.ok-no-content-type",
+    "tokens.get.ok",
+    "tokens.other.405",
+];
+
 pub async fn check(c: &mut crate::checker::Checker) {
-    c.path = "/tokens";
+    c.path = "/tokens".into();
 
     // error cases
     let response = c.post_no_body("no body/content-length", StatusCode::BAD_REQUEST).await;
-    c.check_error_response_multi(response, vec!["body", "content-length"]);
+    c.check_error_response_multi(response, "body_missing", vec!["body", "content-length"]);
 
     let response = c.post_bad_content_type(
         "content-type other than null or application/json",
         StatusCode::UNSUPPORTED_MEDIA_TYPE,
     ).await;
-    c.check_error_response_multi(response, vec!["content-type", "unsupported media type"]);
+    c.check_error_response_multi(response, "content_type_unsupported", vec!["content-type", "unsupported media type"]);
 
     let response = c.post("can't parse json", "not json".into(), StatusCode::BAD_REQUEST).await;
-    c.check_error_response(response, "parse");
+    c.check_error_response(response, "json_invalid", "parse");
 
     let response = c.post( "missing email", r#"{}"#.into(), StatusCode::BAD_REQUEST).await;
-    c.check_error_response(response, "email");
+    c.check_error_response(response, "email_missing", "email");
 
     let response = c.post( "email must be string", r#"{"email":123}"#.into(), StatusCode::BAD_REQUEST).await;
-    c.check_error_response(response, "string");
+    c.check_error_response(response, "email_not_string", "string");
 
-    let mut long_email: String = "".into();
-    for _ in 0..14 {
-        long_email = long_email + "01234567890";
-    }
-    long_email = long_email + "@example.com";
+    let max_length = c.config.email_max_length as usize;
+    let long_email = format!("{}@example.com", "0".repeat(max_length.saturating_sub(11)));
     let response = c.post("email too long", format!(r#"{{"email":"{}"}}"#, long_email), StatusCode::BAD_REQUEST).await;
-    c.check_error_response(response, "150");
+    c.check_error_response(response, "email_too_long", &max_length.to_string());
 
     let email_1 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
 
     let response = c.post("missing password", format!(r#"{{"email":"{}"}}"#, email_1), StatusCode::BAD_REQUEST).await;
-    c.check_error_response(response, "password");
+    c.check_error_response(response, "password_missing", "password");
 
     let response = c.post(
         "password must be string",
         format!(r#"{{ "email": "{}", "password": 123 }}"#, email_1),
         StatusCode::BAD_REQUEST
     ).await;
-    c.check_error_response(response, "string");
+    c.check_error_response(response, "password_not_string", "string");
 
     let response = c.post(
         "missing lifetime",
-        format!(r#"{{"email":"{}","password":"password"}}"#, email_1),
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, c.password()),
         StatusCode::BAD_REQUEST
     ).await;
-    c.check_error_response(response, "lifetime");
+    c.check_error_response(response, "lifetime_missing", "lifetime");
 
     let response = c.post(
         "lifetime must be string",
-        format!(r#"{{"email":"{}","password":"password", "lifetime": 123 }}"#, email_1),
+        format!(r#"{{"email":"{}","password":"{}", "lifetime": 123 }}"#, email_1, c.password()),
         StatusCode::BAD_REQUEST
     ).await;
-    c.check_error_response(response, "string");
+    c.check_error_response(response, "lifetime_not_string", "string");
 
     let response = c.post(
         "invalid lifetime",
-        format!(r#"{{"email":"{}","password":"password", "lifetime": "wrong" }}"#, email_1),
+        format!(r#"{{"email":"{}","password":"{}", "lifetime": "wrong" }}"#, email_1, c.password()),
         StatusCode::BAD_REQUEST
     ).await;
-    c.check_error_response(response, "no-expiration");
+    c.check_error_response(response, "lifetime_invalid", "no-expiration");
 
     let response = c.post(
         "invalid credentials; unknown email",
-        format!(r#"{{"email":"{}", "password": "password", "lifetime": "no-expiration" }}"#, email_1),
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, c.password()),
         StatusCode::BAD_REQUEST
     ).await;
-    c.check_error_response(response, "invalid");
+    c.check_error_response(response, "credentials_invalid", "invalid");
 
-    c.path = "/users";
+    c.path = "/users".into();
     c.post(
         "invalid credentials; wrong password; create",
-        format!(r#"{{"email":"{}","password":"otherpass"}}"#, email_1),
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, c.other_password(1)),
         StatusCode::OK,
     ).await;
-    c.path = "/tokens";
+    c.path = "/tokens".into();
     let response = c.post(
         "invalid credentials; wrong password; check",
-        format!(r#"{{"email":"{}", "password": "password", "lifetime": "no-expiration" }}"#, email_1),
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, c.password()),
         StatusCode::BAD_REQUEST
     ).await;
-    c.check_error_response(response, "invalid");
+    c.check_error_response(response, "credentials_invalid", "invalid");
 
     let response = c.delete("method not allowed", StatusCode::METHOD_NOT_ALLOWED).await;
-    c.check_error_response(response, "method");
+    c.check_error_response(response, "method_not_allowed", "method");
 
     // success cases
     let email_1 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
-    c.path = "/users";
+    c.path = "/users".into();
     c.post(
         "correct response format; create",
-        format!(r#"{{"email":"{}","password":"password"}}"#, email_1),
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, c.password()),
         StatusCode::OK,
     ).await;
-    c.path = "/tokens";
+    c.path = "/tokens".into();
     let (json_response, _) = c.post(
         "correct response format; check",
-        format!(r#"{{"email":"{}", "password": "password", "lifetime": "no-expiration" }}"#, email_1),
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, c.password()),
         StatusCode::OK
     ).await;
     let mut token_1 = None;
@@ -117,41 +139,43 @@ pub async fn check(c: &mut crate::checker::Checker) {
         c.fail("response is not json".into());
     }
 
-    c.post(
-        "lifetime: no-expiration",
-        format!(r#"{{"email":"{}", "password": "password", "lifetime": "no-expiration" }}"#, email_1),
-        StatusCode::OK
-    ).await;
-
-    c.post(
-        "lifetime: remember-me",
-        format!(r#"{{"email":"{}", "password": "password", "lifetime": "remember-me" }}"#, email_1),
-        StatusCode::OK
-    ).await;
-
-    c.post(
-        "lifetime: until-idle",
-        format!(r#"{{"email":"{}", "password": "password", "lifetime": "until-idle" }}"#, email_1),
-        StatusCode::OK
-    ).await;
+    // lifetimes the implementation doesn't support must be rejected
+    let mut tokens_created = 4;
+    for (lifetime, group) in &[
+        ("no-expiration", "lifetime: no-expiration"),
+        ("remember-me", "lifetime: remember-me"),
+        ("until-idle", "lifetime: until-idle"),
+    ] {
+        let supported = c.config.lifetimes.iter().any(|supported| supported == lifetime);
+        let response = c.post(
+            group,
+            format!(r#"{{"email":"{}", "password": "{}", "lifetime": "{}" }}"#, email_1, c.password(), lifetime),
+            if supported { StatusCode::OK } else { StatusCode::BAD_REQUEST },
+        ).await;
+        if supported {
+            tokens_created += 1;
+        } else {
+            c.check_error_response(response, "lifetime_invalid", "lifetime");
+        }
+    }
 
     c.post_content_type(
         "content-type: application/json",
         "application/json",
-        format!(r#"{{"email":"{}", "password": "password", "lifetime": "no-expiration" }}"#, email_1),
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, c.password()),
         StatusCode::OK
     ).await;
 
     c.post_content_type(
         "content-type: application/json;charset=utf-8",
         "application/json;charset=utf-8",
-        format!(r#"{{"email":"{}", "password": "password", "lifetime": "no-expiration" }}"#, email_1),
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, c.password()),
         StatusCode::OK
     ).await;
 
     c.post_no_content_type(
         "no content-type",
-        format!(r#"{{"email":"{}", "password": "password", "lifetime": "no-expiration" }}"#, email_1),
+        format!(r#"{{"email":"{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, c.password()),
         StatusCode::OK
     ).await;
 
@@ -161,7 +185,8 @@ pub async fn check(c: &mut crate::checker::Checker) {
 
             if let Some(property_value) = json_response.get("tokens") {
                 if let Some(tokens) = property_value.as_array() {
-                    c.check(tokens.len() == 7, format!("incorrect number of tokens returned: {}", tokens.len()));
+                    let expected = c.config.token_limit.map_or(tokens_created, |limit| tokens_created.min(limit as usize));
+                    c.check(tokens.len() == expected, format!("incorrect number of tokens returned: {}", tokens.len()));
 
                     let mut token_1_found = false;
                     for token in tokens {
@@ -1,23 +1,29 @@
 use hyper::StatusCode;
 
+pub const COVERS: &[&str] = &[
+    "tokens-current.get.ok",
+    "tokens-current.delete.ok",
+    "tokens-current.other.405",
+];
+
 pub async fn check(c: &mut crate::checker::Checker) {
     // error cases
-    c.path = "/tokens/current";
+    c.path = "/tokens/current".into();
     c.post_no_body("method not allowed", StatusCode::METHOD_NOT_ALLOWED).await;
 
     // success cases
     let email_1 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
 
-    c.path = "/users";
+    c.path = "/users".into();
     c.post(
         "invalid credentials; wrong password; create",
-        format!(r#"{{ "email": "{}", "password": "password" }}"#, email_1),
+        format!(r#"{{ "email": "{}", "password": "{}" }}"#, email_1, c.password()),
         StatusCode::OK,
     ).await;
-    c.path = "/tokens";
+    c.path = "/tokens".into();
     let (json_response, _) = c.post(
         "correct response format; check",
-        format!(r#"{{ "email": "{}", "password": "password", "lifetime": "no-expiration" }}"#, email_1),
+        format!(r#"{{ "email": "{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, c.password()),
         StatusCode::OK,
     ).await;
     let mut token_1 = None;
@@ -37,7 +43,7 @@ pub async fn check(c: &mut crate::checker::Checker) {
         c.fail("token create response was not json".into());
     }
 
-    c.path = "/tokens/current";
+    c.path = "/tokens/current".into();
     if let Some(token_1) = token_1 {
         let (json_response, _) = c.get_with_token(
             "correct response format; check",
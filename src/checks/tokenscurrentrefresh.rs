@@ -1,18 +1,23 @@
 use hyper::StatusCode;
 
+pub const COVERS: &[&str] = &[
+    "tokens-current-refresh.post.ok",
+    "tokens-current-refresh.other.405",
+];
+
 pub async fn check(c: &mut crate::checker::Checker) {
     let email_1 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
 
-    c.path = "/users";
+    c.path = "/users".into();
     c.post(
         "invalid credentials; wrong password; create",
-        format!(r#"{{ "email": "{}", "password": "password" }}"#, email_1),
+        format!(r#"{{ "email": "{}", "password": "{}" }}"#, email_1, c.password()),
         StatusCode::OK,
     ).await;
-    c.path = "/tokens";
+    c.path = "/tokens".into();
     let (json_response, _) = c.post(
         "correct response format; check",
-        format!(r#"{{ "email": "{}", "password": "password", "lifetime": "no-expiration" }}"#, email_1),
+        format!(r#"{{ "email": "{}", "password": "{}", "lifetime": "no-expiration" }}"#, email_1, c.password()),
         StatusCode::OK,
     ).await;
     let mut token_secret = None;
@@ -24,11 +29,11 @@ pub async fn check(c: &mut crate::checker::Checker) {
 
     if let Some(token_secret) = token_secret {
         // error cases
-        c.path = "/tokens/current/refresh";
+        c.path = "/tokens/current/refresh".into();
         c.get_with_token("method not allowed", token_secret.clone(), StatusCode::METHOD_NOT_ALLOWED).await;
 
         // success cases
-        c.path = "/tokens/current/refresh";
+        c.path = "/tokens/current/refresh".into();
         let (json_response, _) = c.post_with_token(
             "correct response format; check",
             token_secret,
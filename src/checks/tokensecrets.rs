@@ -0,0 +1,137 @@
+use std::collections::{HashMap, HashSet};
+
+pub const COVERS: &[&str] = &[
+    "token-secrets.length",
+    "token-secrets.charset",
+    "token-secrets.unique",
+    "token-secrets.entropy",
+    "token-secrets.not-sequential",
+    "token-secrets.id-not-derived",
+];
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    let samples = c.config.token_limit.map_or(c.config.token_samples, |limit| c.config.token_samples.min(limit));
+    if samples < 2 {
+        c.skip("secret analysis", &format!("only {} token can be collected, need at least 2 to analyze", samples));
+        return
+    }
+    // log_in fails if the server returns fewer tokens than asked for
+    let tokens: Vec<Token> = match c.log_in("secret analysis; collect", samples as usize).await {
+        Some(identity) => identity.tokens.into_iter().map(|(id, secret)| Token { id, secret }).collect(),
+        None => return,
+    };
+
+    // format
+    for token in &tokens {
+        c.check(
+            token.secret.chars().count() >= c.config.min_secret_length as usize,
+            format!("secret is shorter than {} chars: '{}'", c.config.min_secret_length, token.secret),
+        );
+        c.check(
+            token.secret.chars().all(is_cookie_octet),
+            format!("secret contains characters not allowed in a cookie value: '{}'", token.secret),
+        );
+    }
+
+    // uniqueness
+    let secrets: HashSet<&String> = tokens.iter().map(|token| &token.secret).collect();
+    c.check(
+        secrets.len() == tokens.len(),
+        format!("{} of {} secrets are duplicates", tokens.len() - secrets.len(), tokens.len()),
+    );
+    let ids: HashSet<&String> = tokens.iter().map(|token| &token.id).collect();
+    c.check(
+        ids.len() == tokens.len(),
+        format!("{} of {} ids are duplicates", tokens.len() - ids.len(), tokens.len()),
+    );
+
+    // randomness
+    let min_length = tokens.iter().map(|token| token.secret.chars().count()).min().unwrap_or(0);
+    let bits_per_char = shannon_entropy(tokens.iter().flat_map(|token| token.secret.chars()));
+    let estimated_bits = bits_per_char * min_length as f64;
+    c.check(
+        estimated_bits >= c.config.min_secret_bits as f64,
+        format!(
+            "estimated secret entropy is {:.0} bits ({} chars at {:.2} bits/char), minimum is {}",
+            estimated_bits, min_length, bits_per_char, c.config.min_secret_bits,
+        ),
+    );
+
+    for pair in tokens.windows(2) {
+        let (previous, next) = (&pair[0].secret, &pair[1].secret);
+        let similarity = positional_similarity(previous, next);
+        if !c.check(
+            similarity < 0.5,
+            format!("consecutive secrets look sequential ({:.0}% same position/char): '{}', '{}'", similarity * 100.0, previous, next),
+        ) {
+            break
+        }
+    }
+
+    // id must not reveal the secret
+    for token in &tokens {
+        let shared = longest_common_substring(&token.id.to_lowercase(), &token.secret.to_lowercase());
+        c.check(
+            token.id != token.secret && shared < 8 && !(token.id.len() >= 4 && token.secret.contains(&token.id)),
+            format!("id '{}' shares {} chars with its secret '{}'", token.id, shared, token.secret),
+        );
+    }
+}
+
+struct Token {
+    id: String,
+    secret: String,
+}
+
+/// Cookie value characters per RFC 6265 section 4.1.1.
+fn is_cookie_octet(ch: char) -> bool {
+    ch.is_ascii_graphic() && !matches!(ch, '"' | ',' | ';' | '\\')
+}
+
+/// Bits per character of the observed character distribution, with the
+/// Miller-Madow correction since small samples underestimate entropy.
+fn shannon_entropy(chars: impl Iterator<Item = char>) -> f64 {
+    let mut counts: HashMap<char, u32> = HashMap::new();
+    let mut total = 0;
+    for ch in chars {
+        *counts.entry(ch).or_insert(0) += 1;
+        total += 1;
+    }
+    if total == 0 {
+        return 0.0
+    }
+    let entropy = counts.values().fold(0.0, |entropy, count| {
+        let p = *count as f64 / total as f64;
+        entropy - p * p.log2()
+    });
+    entropy + (counts.len() - 1) as f64 / (2.0 * total as f64 * std::f64::consts::LN_2)
+}
+
+/// Fraction of positions where both strings have the same character. Random
+/// secrets share few positions, counters and timestamps share most.
+fn positional_similarity(a: &str, b: &str) -> f64 {
+    let length = a.chars().count().max(b.chars().count());
+    if length == 0 {
+        return 1.0
+    }
+    let same = a.chars().zip(b.chars()).filter(|(a, b)| a == b).count();
+    same as f64 / length as f64
+}
+
+fn longest_common_substring(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous = vec![0; b.len() + 1];
+    let mut longest = 0;
+    for i in 1..=a.len() {
+        let mut current = vec![0; b.len() + 1];
+        for j in 1..=b.len() {
+            if a[i - 1] == b[j - 1] {
+                current[j] = previous[j - 1] + 1;
+                longest = longest.max(current[j]);
+            }
+        }
+        previous = current;
+    }
+    longest
+}
@@ -1,68 +1,85 @@
 use hyper::StatusCode;
 
+pub const COVERS: &[&str] = &[
+    "users.post.no-body",
+    "users.post.content-type",
+    "users.post.parse",
+    "users.post.email-missing",
+    "users.post.email-string",
+    "users.post.email-too-long",
+    "users.post.password-missing",
+    "users.post.password-string",
+    "users.post.email-in-use",
+    "users.post.ok",
+    "users.post.ok-json",
+    "users.post.ok-json-utf8",
+    "users.post.ok-no-content-type",
+    "users.other.405",
+];
+
 pub async fn check(c: &mut crate::checker::Checker) {
-    c.path = "/users";
+    c.path = "/users".into();
 
     // error cases
     let response = c.post_no_body("no body/content-length", StatusCode::BAD_REQUEST).await;
-    c.check_error_response_multi(response, vec!["body", "content-length"]);
+    c.check_error_response_multi(response, "body_missing", vec!["body", "content-length"]);
 
     let response = c.post_bad_content_type(
         "content-type other than null or application/json",
         StatusCode::UNSUPPORTED_MEDIA_TYPE,
     ).await;
-    c.check_error_response_multi(response, vec!["content-type", "unsupported media type"]);
+    c.check_error_response_multi(response, "content_type_unsupported", vec!["content-type", "unsupported media type"]);
 
     let response = c.post("can't parse json", "not json".into(), StatusCode::BAD_REQUEST).await;
-    c.check_error_response(response, "parse");
+    c.check_error_response(response, "json_invalid", "parse");
 
     let response = c.post( "missing email", r#"{}"#.into(), StatusCode::BAD_REQUEST).await;
-    c.check_error_response(response, "email");
+    c.check_error_response(response, "email_missing", "email");
 
     let response = c.post( "email must be string", r#"{"email":123}"#.into(), StatusCode::BAD_REQUEST).await;
-    c.check_error_response(response, "string");
+    c.check_error_response(response, "email_not_string", "string");
 
-    let mut long_email: String = "".into();
-    for _ in 0..14 {
-        long_email = long_email + "01234567890";
-    }
-    long_email = long_email + "@example.com";
+    let max_length = c.config.email_max_length as usize;
+    let long_email = format!("{}@example.com", "0".repeat(max_length.saturating_sub(11)));
     let response = c.post("email too long", format!(r#"{{"email":"{}"}}"#, long_email), StatusCode::BAD_REQUEST).await;
-    c.check_error_response(response, "150");
+    c.check_error_response(response, "email_too_long", &max_length.to_string());
 
     let email_1 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
 
     let response = c.post("missing password", format!(r#"{{"email":"{}"}}"#, email_1), StatusCode::BAD_REQUEST).await;
-    c.check_error_response(response, "password");
+    c.check_error_response(response, "password_missing", "password");
 
     let response = c.post(
         "password must be string",
         format!(r#"{{ "email": "{}", "password": 123 }}"#, email_1),
         StatusCode::BAD_REQUEST
     ).await;
-    c.check_error_response(response, "string");
-
-    c.post(
-        "identity with given email already exists - create",
-        format!(r#"{{"email":"{}","password":"password"}}"#, email_1),
-        StatusCode::OK,
-    ).await;
-    let response = c.post(
-        "identity with given email already exists - check",
-        format!(r#"{{"email":"{}","password":"password"}}"#, email_1),
-        StatusCode::BAD_REQUEST
-    ).await;
-    c.check_error_response(response, "in use");
+    c.check_error_response(response, "password_not_string", "string");
+
+    // with --strict-enumeration duplicates are checked by checks::enumeration instead
+    if !c.config.strict_enumeration {
+        c.post(
+            "identity with given email already exists - create",
+            format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, c.password()),
+            StatusCode::OK,
+        ).await;
+        let response = c.post(
+            "identity with given email already exists - check",
+            format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, c.password()),
+            StatusCode::BAD_REQUEST
+        ).await;
+        c.check_error_response(response, "email_in_use", "in use");
+    }
 
     let response = c.get("method not allowed", StatusCode::METHOD_NOT_ALLOWED).await;
-    c.check_error_response(response, "method");
+    c.check_error_response(response, "method_not_allowed", "method");
 
 
     // success cases
     let email_1 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
     let (json_response, _) = c.post(
         "correct response format",
-        format!(r#"{{"email":"{}","password":"password"}}"#, email_1),
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, c.password()),
         StatusCode::OK,
     ).await;
 
@@ -82,7 +99,7 @@ pub async fn check(c: &mut crate::checker::Checker) {
     c.post_content_type(
         "content-type: application/json;charset=utf-8",
         "application/json",
-        format!(r#"{{"email":"{}","password":"password"}}"#, email_1),
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, c.password()),
         StatusCode::OK,
     ).await;
 
@@ -90,14 +107,14 @@ pub async fn check(c: &mut crate::checker::Checker) {
     c.post_content_type(
         "content-type: application/json;charset=utf-8",
         "application/json;charset=utf-8",
-        format!(r#"{{"email":"{}","password":"password"}}"#, email_1),
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, c.password()),
         StatusCode::OK,
     ).await;
 
     let email_1 = format!("test+{:0>8x}@example.com", rand::random::<u32>());
     c.post_no_content_type(
         "no content-type",
-        format!(r#"{{"email":"{}","password":"password"}}"#, email_1),
+        format!(r#"{{"email":"{}","password":"{}"}}"#, email_1, c.password()),
         StatusCode::OK,
     ).await;
 }
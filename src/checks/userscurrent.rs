@@ -0,0 +1,66 @@
+use hyper::{Method, StatusCode};
+
+pub const COVERS: &[&str] = &[
+    "users-current.get.no-token",
+    "users-current.get.ok",
+    "users-current.delete.no-token",
+    "users-current.delete.ok",
+    "users-current.delete.tokens",
+    "users-current.delete.credentials",
+    "users-current.delete.re-register",
+    "users-current.other.405",
+];
+
+pub async fn check(c: &mut crate::checker::Checker) {
+    let (email_1, id, secrets) = match c.log_in("current identity; log in", 2).await {
+        Some(identity) => (identity.email, identity.id, identity.tokens.into_iter().map(|(_, secret)| secret).collect::<Vec<String>>()),
+        None => return,
+    };
+    let secret = secrets[0].clone();
+
+    // error cases
+    c.path = "/users/current".into();
+    c.get("current identity; no token", StatusCode::UNAUTHORIZED).await;
+    c.delete("current identity; delete without token", StatusCode::UNAUTHORIZED).await;
+
+    let response = c.request_with_token("current identity; method not allowed", Method::POST, secret.clone(), StatusCode::METHOD_NOT_ALLOWED).await;
+    c.check_error_response(response, "method_not_allowed", "method");
+
+    // success cases
+    let (json_response, _) = c.get_with_token("current identity; correct response format", secret.clone(), StatusCode::OK).await;
+    if let Some(json_response) = json_response {
+        if let Some(email) = c.get_property_string(&json_response, "email") {
+            c.check(
+                email == email_1,
+                format!("expected email to be '{}' but got '{}'", email_1, email)
+            );
+        }
+        if let Some(current_id) = c.get_property_i64(&json_response, "id") {
+            c.check(
+                Some(current_id) == id,
+                format!("expected id to be {:?} as on create but got {}", id, current_id)
+            );
+        }
+    } else {
+        c.fail("response was not json".into());
+    }
+
+    let (json_response, _) = c.delete_with_token("current identity; delete", secret.clone(), StatusCode::OK).await;
+    if let Some(json_response) = json_response {
+        c.get_property_string(&json_response, "success");
+    } else {
+        c.fail("response was not json".into());
+    }
+
+    c.path = "/tokens/current".into();
+    for secret in secrets {
+        c.get_with_token("current identity; tokens deleted with the identity", secret, StatusCode::UNAUTHORIZED).await;
+    }
+
+    let response = c.create_token("current identity; deleted identity can't log in", &email_1, &c.password(), StatusCode::BAD_REQUEST).await;
+    c.check_error_response(response, "credentials_invalid", "invalid");
+
+    // the email is free again, and only the new identity's password works
+    c.register("current identity; re-register deleted email", &email_1, &c.other_password(2), StatusCode::OK).await;
+    c.create_token("current identity; log in to re-registered identity", &email_1, &c.other_password(2), StatusCode::OK).await;
+}
@@ -0,0 +1,222 @@
+use hyper::{Body, Method, Request};
+
+use crate::checker::Checker;
+use crate::config::Config;
+use crate::groups::GROUPS;
+
+/// Runs discovery and every check group against each of `config.base_urls`
+/// and prints which pass per implementation. Returns false if any failed
+/// anywhere.
+pub async fn matrix(config: &Config) -> bool {
+    let mut columns = vec![];
+    for base_url in &config.base_urls {
+        println!("== {}", base_url);
+        let mut config = config.clone();
+        config.base_url = base_url.clone();
+        let mut c = Checker::new(config);
+        crate::discovery::discover(&mut c).await;
+
+        let mut results = vec![if !c.config.discover {
+            "-".to_string()
+        } else if c.failed > 0 {
+            format!("FAIL {}", c.failed)
+        } else {
+            "pass".to_string()
+        }];
+        for group in GROUPS {
+            if !group.is_selected(c.config.spec_version, &c.config.profiles) {
+                results.push("-".to_string());
+                continue
+            }
+            let (failed, skipped) = (c.failed, c.skipped);
+            crate::groups::run(group, &mut c).await;
+            results.push(if c.failed > failed {
+                format!("FAIL {}", c.failed - failed)
+            } else if c.skipped > skipped {
+                "skip".to_string()
+            } else {
+                "pass".to_string()
+            });
+        }
+        println!();
+        columns.push(results);
+    }
+
+    let width = 14;
+    print!("{:<22}", "");
+    for i in 0..config.base_urls.len() {
+        print!("{:<width$}", format!("[{}]", i + 1), width = width);
+    }
+    println!();
+    let names = std::iter::once("discovery").chain(GROUPS.iter().map(|group| group.name));
+    for (row, name) in names.enumerate() {
+        print!("{:<22}", name);
+        for column in &columns {
+            print!("{:<width$}", column[row], width = width);
+        }
+        println!();
+    }
+    println!();
+    for (i, base_url) in config.base_urls.iter().enumerate() {
+        println!("[{}] {}", i + 1, base_url);
+    }
+
+    !columns.iter().flatten().any(|result| result.starts_with("FAIL"))
+}
+
+/// Sends the same sequence of requests to every implementation and prints
+/// the steps where status, error message or response shape differ. Each
+/// difference may be allowed by the spec; this is for keeping
+/// implementations alike. Returns false if any step diverged.
+pub async fn differential(config: &Config) -> bool {
+    let client = hyper::Client::new();
+    let email = format!("test+{:0>8x}@example.com", rand::random::<u32>());
+    let password = crate::checker::random_password(crate::checker::password_length(config));
+    let steps = steps(&email, &password);
+
+    let mut secrets: Vec<Option<String>> = vec![None; config.base_urls.len()];
+    let mut diverged = 0;
+    for step in &steps {
+        let mut outcomes = vec![];
+        for (i, base_url) in config.base_urls.iter().enumerate() {
+            let cookie = if step.authenticated { secrets[i].clone() } else { None };
+            let outcome = send(&client, base_url, step, cookie).await;
+            if step.captures_secret {
+                if let Some(secret) = outcome.json.as_ref().and_then(|json| json["secret"].as_str()) {
+                    secrets[i] = Some(secret.to_string());
+                }
+            }
+            outcomes.push(outcome);
+        }
+
+        let differs = |value: &dyn Fn(&Outcome) -> String| outcomes.iter().any(|outcome| value(outcome) != value(&outcomes[0]));
+        let status = |outcome: &Outcome| outcome.status.clone();
+        let error = |outcome: &Outcome| outcome.json.as_ref().and_then(|json| json["error"].as_str()).unwrap_or("").to_string();
+        let shape = |outcome: &Outcome| outcome.json.as_ref().map(shape).unwrap_or_else(|| "(not json)".into());
+
+        let mut differences = vec![];
+        for (name, value) in &[("status", &status as &dyn Fn(&Outcome) -> String), ("error", &error), ("shape", &shape)] {
+            if differs(*value) {
+                differences.push((*name, outcomes.iter().map(value).collect::<Vec<String>>()));
+            }
+        }
+        if differences.is_empty() {
+            continue
+        }
+
+        diverged += 1;
+        println!("Diverged: {} {} - {}", step.method, step.path, step.description);
+        for (name, values) in differences {
+            for (i, value) in values.iter().enumerate() {
+                println!("  {:<7}[{}] {}", name, i + 1, value);
+            }
+        }
+    }
+
+    println!();
+    for (i, base_url) in config.base_urls.iter().enumerate() {
+        println!("[{}] {}", i + 1, base_url);
+    }
+    println!("\n{} Steps / {} Diverged", steps.len(), diverged);
+    diverged == 0
+}
+
+struct Step {
+    description: &'static str,
+    method: Method,
+    path: &'static str,
+    body: Option<String>,
+    authenticated: bool,
+    captures_secret: bool,
+}
+
+struct Outcome {
+    status: String,
+    json: Option<serde_json::Value>,
+}
+
+fn steps(email: &str, password: &str) -> Vec<Step> {
+    let step = |description, method, path, body: Option<String>| Step {
+        description,
+        method,
+        path,
+        body,
+        authenticated: false,
+        captures_secret: false,
+    };
+    let authenticated = |description, method, path| Step {
+        authenticated: true,
+        ..step(description, method, path, None)
+    };
+    let credentials = |password: &str, lifetime: &str| {
+        Some(format!(r#"{{"email":"{}","password":"{}","lifetime":"{}"}}"#, email, password, lifetime))
+    };
+
+    vec![
+        step("no body", Method::POST, "/users", None),
+        step("can't parse json", Method::POST, "/users", Some("not json".into())),
+        step("missing email", Method::POST, "/users", Some("{}".into())),
+        step("email must be string", Method::POST, "/users", Some(r#"{"email":123}"#.into())),
+        step("missing password", Method::POST, "/users", Some(format!(r#"{{"email":"{}"}}"#, email))),
+        step("create", Method::POST, "/users", Some(format!(r#"{{"email":"{}","password":"{}"}}"#, email, password))),
+        step("email in use", Method::POST, "/users", Some(format!(r#"{{"email":"{}","password":"{}"}}"#, email, password))),
+        step("method not allowed", Method::GET, "/users", None),
+        step("no body", Method::POST, "/tokens", None),
+        step("missing lifetime", Method::POST, "/tokens", Some(format!(r#"{{"email":"{}","password":"{}"}}"#, email, password))),
+        step("invalid lifetime", Method::POST, "/tokens", credentials(password, "wrong")),
+        step("wrong password", Method::POST, "/tokens", credentials("wrong", "no-expiration")),
+        Step {
+            captures_secret: true,
+            ..step("create", Method::POST, "/tokens", credentials(password, "no-expiration"))
+        },
+        step("method not allowed", Method::DELETE, "/tokens", None),
+        step("no token", Method::GET, "/tokens", None),
+        authenticated("list", Method::GET, "/tokens"),
+        authenticated("current", Method::GET, "/tokens/current"),
+        authenticated("valid", Method::GET, "/tokens/current/valid"),
+        authenticated("refresh", Method::POST, "/tokens/current/refresh"),
+        authenticated("method not allowed", Method::GET, "/tokens/current/refresh"),
+        authenticated("delete", Method::DELETE, "/tokens/current"),
+        authenticated("deleted token", Method::GET, "/tokens/current"),
+        step("not found", Method::GET, "/not-found", None),
+    ]
+}
+
+async fn send(client: &hyper::Client<hyper::client::HttpConnector, Body>, base_url: &str, step: &Step, secret: Option<String>) -> Outcome {
+    let mut request = Request::builder()
+        .method(step.method.clone())
+        .uri(format!("{}{}", base_url, step.path));
+    if let Some(secret) = secret {
+        request = request.header("cookie", format!("token={}", secret));
+    }
+    if step.body.is_some() {
+        request = request.header("content-type", "application/json");
+    }
+    let body = step.body.clone().map(Body::from).unwrap_or_else(Body::empty);
+
+    match client.request(request.body(body).unwrap()).await {
+        Ok(response) => {
+            let status = response.status().to_string();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap_or_default();
+            Outcome { status, json: serde_json::from_slice(&body).ok() }
+        }
+        Err(e) => Outcome { status: format!("error: {}", e), json: None },
+    }
+}
+
+/// Property names and value types, ignoring the values themselves.
+fn shape(json: &serde_json::Value) -> String {
+    match json {
+        serde_json::Value::Null => "null".into(),
+        serde_json::Value::Bool(_) => "boolean".into(),
+        serde_json::Value::Number(number) if number.is_f64() => "number".into(),
+        serde_json::Value::Number(_) => "integer".into(),
+        serde_json::Value::String(_) => "string".into(),
+        serde_json::Value::Array(items) => format!("[{}]", items.first().map(shape).unwrap_or_default()),
+        serde_json::Value::Object(object) => {
+            let mut properties: Vec<String> = object.iter().map(|(name, value)| format!("{}: {}", name, shape(value))).collect();
+            properties.sort();
+            format!("{{{}}}", properties.join(", "))
+        }
+    }
+}
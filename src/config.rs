@@ -0,0 +1,289 @@
+use crate::spec::{self, Profile, Version};
+
+#[derive(Clone)]
+pub struct Config {
+    pub command: Command,
+    pub base_url: String,
+    /// Every --base-url given, for compare.
+    pub base_urls: Vec<String>,
+    pub differential: bool,
+    pub spec_version: Version,
+    /// Always includes core.
+    pub profiles: Vec<Profile>,
+    pub discover: bool,
+    pub email_max_length: u32,
+    /// Password length limits in unicode code points, for password-policy.
+    pub password_min_length: u32,
+    pub password_max_length: u32,
+    /// File of passwords the server rejects as breached, one per line.
+    pub breached_passwords: Option<String>,
+    pub lifetimes: Vec<String>,
+    pub token_limit: Option<u32>,
+    /// Flags given on the command line, which take precedence over the
+    /// server's capability document.
+    pub explicit: Vec<String>,
+    pub db_url: Option<String>,
+    pub password_hash_policy: PasswordHashPolicy,
+    pub token_samples: u32,
+    pub min_secret_length: u32,
+    pub min_secret_bits: u32,
+    pub allow_insecure_cookie: bool,
+    pub timing_samples: u32,
+    pub strict_enumeration: bool,
+    pub rate_limit: Option<RateLimit>,
+    /// Origins the server allows cross-origin, credentialed requests from.
+    pub cors_origins: Vec<String>,
+    pub csrf: Csrf,
+    pub strict_schema: bool,
+    /// HAR file to record exchanges to, or for replay to read.
+    pub har: Option<String>,
+    /// Directory to write a curl script per failed request to.
+    pub repro: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Command {
+    Check,
+    OpenApi,
+    Coverage,
+    Compare,
+    Replay,
+}
+
+/// Login throttling the server is expected to apply per email.
+#[derive(Clone)]
+pub struct RateLimit {
+    pub attempts: u32,
+    pub window_seconds: u32,
+}
+
+/// How the server tells same-site requests from cross-site ones on
+/// cookie-authenticated routes that change state.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Csrf {
+    /// rejects an origin or referer other than its own
+    Origin,
+    /// requires an x-requested-with header, which forms can't send
+    Header,
+    /// sets a csrf cookie on login, which must be repeated in x-csrf-token
+    DoubleSubmit,
+}
+
+impl Csrf {
+    pub fn parse(name: &str) -> Option<Csrf> {
+        match name {
+            "origin" => Some(Csrf::Origin),
+            "header" => Some(Csrf::Header),
+            "double-submit" => Some(Csrf::DoubleSubmit),
+            _ => None,
+        }
+    }
+}
+
+/// Minimum work factors a stored password hash must meet.
+#[derive(Clone)]
+pub struct PasswordHashPolicy {
+    pub bcrypt_cost: u32,
+    pub pbkdf2_iterations: u32,
+    pub scrypt_log_n: u32,
+    pub argon2_memory_kib: u32,
+}
+
+impl Config {
+    pub fn from_args() -> Config {
+        let mut config = Config {
+            command: Command::Check,
+            base_url: "http://localhost:3000".into(),
+            base_urls: vec![],
+            differential: false,
+            spec_version: spec::LATEST,
+            profiles: vec![Profile::Core],
+            discover: true,
+            email_max_length: 150,
+            password_min_length: 8,
+            password_max_length: 64,
+            breached_passwords: None,
+            lifetimes: vec!["until-idle".into(), "remember-me".into(), "no-expiration".into()],
+            token_limit: None,
+            explicit: vec![],
+            db_url: None,
+            password_hash_policy: PasswordHashPolicy {
+                bcrypt_cost: 10,
+                pbkdf2_iterations: 100_000,
+                scrypt_log_n: 14,
+                argon2_memory_kib: 19_456,
+            },
+            token_samples: 20,
+            min_secret_length: 16,
+            min_secret_bits: 64,
+            allow_insecure_cookie: false,
+            timing_samples: 50,
+            strict_enumeration: false,
+            rate_limit: None,
+            cors_origins: vec![],
+            csrf: Csrf::Origin,
+            strict_schema: false,
+            har: None,
+            repro: None,
+        };
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg.starts_with("--") {
+                config.explicit.push(arg.clone());
+            }
+            match arg.as_str() {
+                "openapi" => config.command = Command::OpenApi,
+                "coverage" => config.command = Command::Coverage,
+                "compare" => config.command = Command::Compare,
+                "replay" => config.command = Command::Replay,
+                "--base-url" => {
+                    config.base_url = value(&arg, args.next());
+                    config.base_urls.push(config.base_url.clone());
+                }
+                "--differential" => config.differential = true,
+                "--spec" => {
+                    let name = value(&arg, args.next());
+                    config.spec_version = Version::parse(&name)
+                        .unwrap_or_else(|| usage(&format!("unknown spec version '{}'", name)));
+                }
+                "--profile" => {
+                    let name = value(&arg, args.next());
+                    let profile = Profile::parse(&name)
+                        .unwrap_or_else(|| usage(&format!("unknown profile '{}'", name)));
+                    if !config.profiles.contains(&profile) {
+                        config.profiles.push(profile);
+                    }
+                }
+                "--no-discovery" => config.discover = false,
+                "--email-max-length" => config.email_max_length = number(&arg, args.next()),
+                "--password-min-length" => config.password_min_length = number(&arg, args.next()),
+                "--password-max-length" => config.password_max_length = number(&arg, args.next()),
+                "--breached-passwords" => config.breached_passwords = Some(value(&arg, args.next())),
+                "--lifetimes" => {
+                    config.lifetimes = value(&arg, args.next()).split(',').map(|lifetime| lifetime.trim().to_string()).collect();
+                }
+                "--token-limit" => config.token_limit = Some(number(&arg, args.next())),
+                "--db-url" => config.db_url = Some(value(&arg, args.next())),
+                "--min-bcrypt-cost" => config.password_hash_policy.bcrypt_cost = number(&arg, args.next()),
+                "--min-pbkdf2-iterations" => config.password_hash_policy.pbkdf2_iterations = number(&arg, args.next()),
+                "--min-scrypt-log-n" => config.password_hash_policy.scrypt_log_n = number(&arg, args.next()),
+                "--min-argon2-memory-kib" => config.password_hash_policy.argon2_memory_kib = number(&arg, args.next()),
+                "--token-samples" => config.token_samples = number(&arg, args.next()),
+                "--min-secret-length" => config.min_secret_length = number(&arg, args.next()),
+                "--min-secret-bits" => config.min_secret_bits = number(&arg, args.next()),
+                "--allow-insecure-cookie" => config.allow_insecure_cookie = true,
+                "--timing-samples" => config.timing_samples = number(&arg, args.next()),
+                "--strict-enumeration" => config.strict_enumeration = true,
+                "--rate-limit-attempts" => {
+                    let attempts = number(&arg, args.next());
+                    config.rate_limit.get_or_insert(RateLimit { attempts, window_seconds: 60 }).attempts = attempts;
+                }
+                "--rate-limit-window" => {
+                    let window_seconds = number(&arg, args.next());
+                    config.rate_limit.get_or_insert(RateLimit { attempts: 5, window_seconds }).window_seconds = window_seconds;
+                }
+                "--cors-origin" => config.cors_origins.push(value(&arg, args.next())),
+                "--csrf" => {
+                    let name = value(&arg, args.next());
+                    config.csrf = Csrf::parse(&name)
+                        .unwrap_or_else(|| usage(&format!("unknown csrf protection '{}'", name)));
+                }
+                "--strict-schema" => config.strict_schema = true,
+                "--har" => config.har = Some(value(&arg, args.next())),
+                "--repro" => config.repro = Some(value(&arg, args.next())),
+                _ => usage(&format!("unknown argument '{}'", arg)),
+            }
+        }
+
+        // these only mean something to their profile, so they select it
+        let implied = [(config.rate_limit.is_some(), Profile::RateLimiting), (config.db_url.is_some(), Profile::DbInspection)];
+        for (given, profile) in implied {
+            if given && !config.profiles.contains(&profile) {
+                config.profiles.push(profile);
+            }
+        }
+
+        if config.command == Command::Compare && config.base_urls.len() < 2 {
+            usage("compare needs at least two --base-url");
+        }
+        if config.password_min_length > config.password_max_length {
+            usage("--password-min-length is more than --password-max-length");
+        }
+        if config.command == Command::Replay && config.har.is_none() {
+            usage("replay needs --har <file>");
+        }
+
+        config
+    }
+
+    pub fn is_explicit(&self, flag: &str) -> bool {
+        self.explicit.iter().any(|explicit| explicit == flag)
+    }
+}
+
+fn value(flag: &str, value: Option<String>) -> String {
+    match value {
+        Some(value) => value,
+        None => usage(&format!("missing value for '{}'", flag)),
+    }
+}
+
+fn number(flag: &str, value: Option<String>) -> u32 {
+    let value = self::value(flag, value);
+    match value.parse() {
+        Ok(number) => number,
+        Err(_) => usage(&format!("'{}' is not a number for '{}'", value, flag)),
+    }
+}
+
+fn usage(error: &str) -> ! {
+    eprintln!("error: {}", error);
+    eprintln!();
+    eprintln!("usage: auth-spec-checks [command] [options]");
+    eprintln!();
+    eprintln!("commands:");
+    eprintln!("  (none)                         run the checks against --base-url");
+    eprintln!("  openapi                        print the OpenAPI document for the spec");
+    eprintln!("  coverage                       report which check group covers each checks.txt item");
+    eprintln!("  compare                        run the checks against every --base-url and show a matrix,");
+    eprintln!("                                 or with --differential show where their responses diverge");
+    eprintln!("  replay                         re-send the requests recorded in --har against --base-url");
+    eprintln!();
+    eprintln!("options:");
+    eprintln!("  --base-url <url>               server to check (default http://localhost:3000), repeat for compare");
+    eprintln!("  --differential                 compare: send the same requests to each server and diff them");
+    eprintln!("  --spec <version>               spec version to check, v1, v2 or v3 (default {})", spec::LATEST.name());
+    eprintln!("  --profile <profile>            also check an optional profile, repeatable:");
+    eprintln!("                                 cookies, rate-limiting, test-clock, db-inspection, problem-json, cors,");
+    eprintln!("                                 csrf, bearer, password-policy");
+    eprintln!("  --no-discovery                 don't read /.well-known/auth-spec, only use these options");
+    eprintln!("  --email-max-length <n>         longest email the server accepts (default 150)");
+    eprintln!("  --password-min-length <n>      password-policy: shortest password accepted (default 8)");
+    eprintln!("  --password-max-length <n>      password-policy: longest password accepted (default 64)");
+    eprintln!("  --breached-passwords <file>    password-policy: passwords the server rejects, one per line");
+    eprintln!("  --lifetimes <a,b,..>           token lifetimes the server supports (default all three)");
+    eprintln!("  --token-limit <n>              most active tokens an identity may have (default none)");
+    eprintln!("  --db-url <url>                 postgres url for db inspection checks, passed to psql,");
+    eprintln!("                                 selects the db-inspection profile");
+    eprintln!("  --min-bcrypt-cost <n>          minimum bcrypt cost (default 10)");
+    eprintln!("  --min-pbkdf2-iterations <n>    minimum pbkdf2 iterations (default 100000)");
+    eprintln!("  --min-scrypt-log-n <n>         minimum scrypt log2(N) (default 14)");
+    eprintln!("  --min-argon2-memory-kib <n>    minimum argon2 memory in KiB (default 19456)");
+    eprintln!("  --token-samples <n>            tokens to create for secret analysis (default 20)");
+    eprintln!("  --min-secret-length <n>        minimum token secret length (default 16)");
+    eprintln!("  --min-secret-bits <n>          minimum estimated token secret entropy (default 64)");
+    eprintln!("  --allow-insecure-cookie        don't require Secure on the token cookie, for local http");
+    eprintln!("  --timing-samples <n>           logins per failure mode for the timing check (default 50)");
+    eprintln!("  --strict-enumeration           POST /users must not reveal that an email is registered");
+    eprintln!("  --rate-limit-attempts <n>      failed logins per email before 429 (default 5),");
+    eprintln!("                                 with --rate-limit-window selects the rate-limiting profile");
+    eprintln!("  --rate-limit-window <s>        seconds until a throttled email may log in again (default 60)");
+    eprintln!("  --cors-origin <origin>         origin the server allows cross-origin requests from, repeatable");
+    eprintln!("  --csrf <protection>            csrf profile: origin, header or double-submit (default origin)");
+    eprintln!("  --strict-schema                reject nulls and properties the spec doesn't declare");
+    eprintln!("  --har <file>                   record every request and response to a HAR file,");
+    eprintln!("                                 failures are noted in each entry's comment");
+    eprintln!("  --repro <dir>                  write a curl script reproducing each failed request to <dir>");
+    std::process::exit(2);
+}
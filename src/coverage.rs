@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use crate::groups::GROUPS;
+
+const CHECKLIST: &str = include_str!("../checks.txt");
+
+struct Item {
+    id: &'static str,
+    marker: char,
+    line: usize,
+}
+
+/// Prints which check group covers each checks.txt item. Returns false when
+/// an item not marked planned has no check, a check covers an item not marked
+/// started or complete, or a check names an item that doesn't exist.
+pub fn report() -> bool {
+    let items = parse(CHECKLIST);
+    let mut ok = true;
+
+    let mut covered_by: HashMap<&str, Vec<&str>> = HashMap::new();
+    let checker_group = [("checker", crate::checker::COVERS), ("discovery", crate::discovery::COVERS)];
+    let groups = GROUPS.iter().map(|group| (group.name, group.covers)).chain(checker_group.iter().copied());
+    for (name, covers) in groups {
+        for id in covers {
+            if !items.iter().any(|item| item.id == *id) {
+                println!("unknown  {:<44} referenced by {}", id, name);
+                ok = false;
+            }
+            covered_by.entry(id).or_default().push(name);
+        }
+    }
+
+    for (i, item) in items.iter().enumerate() {
+        if items[..i].iter().any(|other| other.id == item.id) {
+            println!("repeated {:<44} line {}", item.id, item.line);
+            ok = false;
+        }
+    }
+
+    let (mut covered, mut planned) = (0, 0);
+    for item in &items {
+        match (item.marker, covered_by.get(item.id)) {
+            ('x', Some(groups)) | ('o', Some(groups)) => {
+                println!("covered  {:<44} {}", item.id, groups.join(", "));
+                covered += 1;
+            }
+            ('p', None) => {
+                println!("planned  {}", item.id);
+                planned += 1;
+            }
+            ('p', Some(groups)) => {
+                println!("unmarked {:<44} covered by {} but marked planned on line {}", item.id, groups.join(", "), item.line);
+                ok = false;
+            }
+            (marker, None) => {
+                let marked = if marker == ' ' { "not marked planned".to_string() } else { format!("marked {}", marker) };
+                println!("missing  {:<44} {} on line {} but no check covers it", item.id, marked, item.line);
+                ok = false;
+            }
+            (_, Some(groups)) => {
+                println!("unmarked {:<44} covered by {} but not marked on line {}", item.id, groups.join(", "), item.line);
+                ok = false;
+            }
+        }
+    }
+
+    println!("\n{} Covered / {} Planned / {} Items", covered, planned, items.len());
+    ok
+}
+
+/// Items are lines with an `[id]`, optionally preceded by an `x`, `o` or `p`
+/// marker.
+fn parse(checklist: &'static str) -> Vec<Item> {
+    checklist
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let line_trimmed = line.trim_start();
+            let (marker, rest) = match line_trimmed.split_once(' ') {
+                Some((marker, rest)) if marker == "x" || marker == "o" || marker == "p" => (marker.chars().next().unwrap(), rest.trim_start()),
+                _ => (' ', line_trimmed),
+            };
+            let id = rest.strip_prefix('[')?.split_once("] ")?.0;
+            Some(Item { id, marker, line: i + 1 })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn parse_reads_markers_ids_and_lines() {
+        let items = parse("Tokens\n\n  x [tokens.create] Create a token\n  o [tokens.delete] Delete it\n    p [tokens.list] List them\n  [tokens.other] Not marked\n");
+        let items: Vec<(&str, char, usize)> = items.iter().map(|item| (item.id, item.marker, item.line)).collect();
+        assert_eq!(items, vec![
+            ("tokens.create", 'x', 3),
+            ("tokens.delete", 'o', 4),
+            ("tokens.list", 'p', 5),
+            ("tokens.other", ' ', 6),
+        ]);
+    }
+
+    #[test]
+    fn parse_skips_lines_without_an_id() {
+        assert!(parse("Legend\n  x: complete\n  x no id here\n  x [missing-close bracket\n").is_empty());
+        // an unknown marker or an id with no description isn't an item
+        assert!(parse("  q [tokens.create] Create\n  x [tokens.create]\n").is_empty());
+    }
+}
@@ -0,0 +1,44 @@
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+
+/// Read-only access to the implementation's database, used by checks that
+/// verify what is stored rather than what is returned. Queries are run through
+/// `psql` so no driver is needed for the checked server's postgres version.
+pub struct Db {
+    url: String,
+}
+
+impl Db {
+    pub fn new(url: String) -> Db {
+        Db { url }
+    }
+
+    /// Runs `query` and returns the first column of the first row, if any.
+    /// `vars` are passed as psql variables and should be referenced in the
+    /// query as `:'name'` so they are quoted by psql.
+    pub async fn query_value(&self, query: &str, vars: &[(&str, &str)]) -> Result<Option<String>, String> {
+        let mut command = tokio::process::Command::new("psql");
+        command
+            .arg(&self.url)
+            .args(["--no-psqlrc", "--tuples-only", "--no-align", "--quiet", "--set=ON_ERROR_STOP=1"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        for (name, value) in vars {
+            command.arg(format!("--set={}={}", name, value));
+        }
+
+        let mut child = command.spawn().map_err(|e| format!("could not run psql: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(query.as_bytes()).await.map_err(|e| format!("could not send query to psql: {}", e))?;
+        }
+
+        let output = child.wait_with_output().await.map_err(|e| format!("psql failed: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("psql failed: {}", String::from_utf8_lossy(&output.stderr).trim()))
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines().next().map(|line| line.to_string()))
+    }
+}
@@ -0,0 +1,101 @@
+use crate::checker::Checker;
+use crate::config::{Csrf, RateLimit};
+use crate::spec::{Profile, Version};
+
+pub const COVERS: &[&str] = &[
+    "capabilities.schema",
+    "capabilities.known",
+];
+
+/// Reads the optional capability document and applies it to `c.config`,
+/// except for settings given explicitly on the command line. Without a
+/// document the command line options and their defaults are used as is.
+pub async fn discover(c: &mut Checker) {
+    if !c.config.discover {
+        return
+    }
+
+    c.path = "/.well-known/auth-spec".into();
+    let document = match c.get_optional("capability document").await {
+        Some(document) => document,
+        None => {
+            println!("No capability document, using command line options");
+            return
+        }
+    };
+
+    if !c.config.is_explicit("--spec") {
+        if let Some(name) = document["spec"].as_str() {
+            match Version::parse(name) {
+                Some(version) => c.config.spec_version = version,
+                None => c.fail(format!("capability document names unknown spec version '{}'", name)),
+            }
+        }
+    }
+
+    if !c.config.is_explicit("--profile") {
+        let mut profiles = vec![Profile::Core];
+        for name in document["profiles"].as_array().into_iter().flatten().filter_map(|name| name.as_str()) {
+            match Profile::parse(name) {
+                Some(profile) if !profiles.contains(&profile) => profiles.push(profile),
+                Some(_) => {}
+                None => c.fail(format!("capability document names unknown profile '{}'", name)),
+            }
+        }
+        c.config.profiles = profiles;
+    }
+
+    if !c.config.is_explicit("--email-max-length") {
+        if let Some(email_max_length) = document["email_max_length"].as_u64() {
+            c.config.email_max_length = email_max_length as u32;
+        }
+    }
+
+    if !c.config.is_explicit("--password-min-length") {
+        if let Some(password_min_length) = document["password_min_length"].as_u64() {
+            c.config.password_min_length = password_min_length as u32;
+        }
+    }
+
+    if !c.config.is_explicit("--password-max-length") {
+        if let Some(password_max_length) = document["password_max_length"].as_u64() {
+            c.config.password_max_length = password_max_length as u32;
+        }
+    }
+
+    if !c.config.is_explicit("--lifetimes") {
+        if let Some(lifetimes) = document["lifetimes"].as_array() {
+            c.config.lifetimes = lifetimes.iter().filter_map(|lifetime| lifetime.as_str()).map(|lifetime| lifetime.to_string()).collect();
+        }
+    }
+
+    if !c.config.is_explicit("--token-limit") {
+        if let Some(token_limit) = document["token_limit"].as_u64() {
+            c.config.token_limit = Some(token_limit as u32);
+        }
+    }
+
+    if !c.config.is_explicit("--rate-limit-attempts") && !c.config.is_explicit("--rate-limit-window") {
+        let rate_limit = &document["rate_limit"];
+        if let (Some(attempts), Some(window_seconds)) = (rate_limit["attempts"].as_u64(), rate_limit["window_seconds"].as_u64()) {
+            c.config.rate_limit = Some(RateLimit { attempts: attempts as u32, window_seconds: window_seconds as u32 });
+        }
+    }
+
+    if !c.config.is_explicit("--cors-origin") {
+        if let Some(origins) = document["cors_origins"].as_array() {
+            c.config.cors_origins = origins.iter().filter_map(|origin| origin.as_str()).map(|origin| origin.to_string()).collect();
+        }
+    }
+
+    if !c.config.is_explicit("--csrf") {
+        if let Some(name) = document["csrf"].as_str() {
+            match Csrf::parse(name) {
+                Some(csrf) => c.config.csrf = csrf,
+                None => c.fail(format!("capability document names unknown csrf protection '{}'", name)),
+            }
+        }
+    }
+
+    println!("Using capability document from {}", c.path);
+}
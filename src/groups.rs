@@ -0,0 +1,191 @@
+use crate::checker::{Checker, Transport};
+use crate::checks;
+use crate::spec::{Profile, Version};
+
+pub struct Group {
+    pub name: &'static str,
+    pub since: Version,
+    pub profile: Profile,
+    /// checks.txt item ids this group implements.
+    pub covers: &'static [&'static str],
+    /// Sends tokens, so runs again with the bearer transport when the
+    /// bearer profile is selected.
+    pub authenticated: bool,
+}
+
+/// Every check group, in the order they run.
+pub const GROUPS: &[Group] = &[
+    Group {
+        name: "tokens",
+        since: Version::V1,
+        profile: Profile::Core,
+        covers: checks::tokens::COVERS,
+        authenticated: true,
+    },
+    Group {
+        name: "tokenscurrent",
+        since: Version::V1,
+        profile: Profile::Core,
+        covers: checks::tokenscurrent::COVERS,
+        authenticated: true,
+    },
+    Group {
+        name: "tokenscurrentrefresh",
+        since: Version::V1,
+        profile: Profile::Core,
+        covers: checks::tokenscurrentrefresh::COVERS,
+        authenticated: true,
+    },
+    Group {
+        name: "users",
+        since: Version::V1,
+        profile: Profile::Core,
+        covers: checks::users::COVERS,
+        authenticated: false,
+    },
+    Group {
+        name: "emails",
+        since: Version::V3,
+        profile: Profile::Core,
+        covers: checks::emails::COVERS,
+        authenticated: false,
+    },
+    Group {
+        name: "userscurrent",
+        since: Version::V3,
+        profile: Profile::Core,
+        covers: checks::userscurrent::COVERS,
+        authenticated: true,
+    },
+    Group {
+        name: "passwordchange",
+        since: Version::V3,
+        profile: Profile::Core,
+        covers: checks::passwordchange::COVERS,
+        authenticated: true,
+    },
+    Group {
+        name: "passwordstorage",
+        since: Version::V2,
+        profile: Profile::DbInspection,
+        covers: checks::passwordstorage::COVERS,
+        authenticated: false,
+    },
+    Group {
+        name: "tokensecrets",
+        since: Version::V2,
+        profile: Profile::Core,
+        covers: checks::tokensecrets::COVERS,
+        authenticated: false,
+    },
+    Group {
+        name: "cookies",
+        since: Version::V2,
+        profile: Profile::Cookies,
+        covers: checks::cookies::COVERS,
+        authenticated: false,
+    },
+    Group {
+        name: "timing",
+        since: Version::V2,
+        profile: Profile::Core,
+        covers: checks::timing::COVERS,
+        authenticated: false,
+    },
+    Group {
+        name: "enumeration",
+        since: Version::V2,
+        profile: Profile::Core,
+        covers: checks::enumeration::COVERS,
+        authenticated: false,
+    },
+    Group {
+        name: "ratelimit",
+        since: Version::V2,
+        profile: Profile::RateLimiting,
+        covers: checks::ratelimit::COVERS,
+        authenticated: false,
+    },
+    Group {
+        name: "methods",
+        since: Version::V2,
+        profile: Profile::Core,
+        covers: checks::methods::COVERS,
+        authenticated: true,
+    },
+    Group {
+        name: "cors",
+        since: Version::V2,
+        profile: Profile::Cors,
+        covers: checks::cors::COVERS,
+        authenticated: false,
+    },
+    Group {
+        name: "csrf",
+        since: Version::V2,
+        profile: Profile::Csrf,
+        covers: checks::csrf::COVERS,
+        authenticated: false,
+    },
+    Group {
+        name: "bearer",
+        since: Version::V2,
+        profile: Profile::Bearer,
+        covers: checks::bearer::COVERS,
+        authenticated: false,
+    },
+    Group {
+        name: "passwordpolicy",
+        since: Version::V3,
+        profile: Profile::PasswordPolicy,
+        covers: checks::passwordpolicy::COVERS,
+        authenticated: false,
+    },
+    Group {
+        name: "leaks",
+        since: Version::V2,
+        profile: Profile::Core,
+        covers: checks::leaks::COVERS,
+        authenticated: true,
+    },
+];
+
+impl Group {
+    pub fn is_selected(&self, version: Version, profiles: &[Profile]) -> bool {
+        self.since <= version && profiles.contains(&self.profile)
+    }
+}
+
+pub async fn run(group: &Group, c: &mut Checker) {
+    run_once(group, c).await;
+    if group.authenticated && c.config.profiles.contains(&Profile::Bearer) {
+        c.transport = Transport::Bearer;
+        run_once(group, c).await;
+        c.transport = Transport::Cookie;
+    }
+}
+
+async fn run_once(group: &Group, c: &mut Checker) {
+    match group.name {
+        "tokens" => checks::tokens::check(c).await,
+        "tokenscurrent" => checks::tokenscurrent::check(c).await,
+        "tokenscurrentrefresh" => checks::tokenscurrentrefresh::check(c).await,
+        "users" => checks::users::check(c).await,
+        "emails" => checks::emails::check(c).await,
+        "userscurrent" => checks::userscurrent::check(c).await,
+        "passwordchange" => checks::passwordchange::check(c).await,
+        "passwordstorage" => checks::passwordstorage::check(c).await,
+        "tokensecrets" => checks::tokensecrets::check(c).await,
+        "cookies" => checks::cookies::check(c).await,
+        "timing" => checks::timing::check(c).await,
+        "enumeration" => checks::enumeration::check(c).await,
+        "ratelimit" => checks::ratelimit::check(c).await,
+        "methods" => checks::methods::check(c).await,
+        "cors" => checks::cors::check(c).await,
+        "csrf" => checks::csrf::check(c).await,
+        "bearer" => checks::bearer::check(c).await,
+        "passwordpolicy" => checks::passwordpolicy::check(c).await,
+        "leaks" => checks::leaks::check(c).await,
+        name => panic!("no check group named '{}'", name),
+    }
+}
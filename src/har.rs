@@ -0,0 +1,271 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hyper::{Body, HeaderMap, Method, Request};
+use serde_json::{json, Value};
+
+use crate::checker::new_email;
+use crate::config::Config;
+use crate::repro::whole_value;
+
+/// One request the checker sent and the response it got back, with the
+/// failures reported while checking that response.
+#[derive(Clone)]
+pub struct Exchange {
+    pub started: SystemTime,
+    pub time: Duration,
+    pub group: &'static str,
+    pub method: Method,
+    pub url: String,
+    pub request_headers: HeaderMap,
+    pub request_body: Vec<u8>,
+    pub status: u16,
+    pub response_headers: HeaderMap,
+    pub response_body: Vec<u8>,
+    pub failures: Vec<String>,
+}
+
+/// Writes the exchanges as a HAR 1.2 log. Failures are in each entry's
+/// `comment`, so failed checks can be found by searching for "Failed:".
+pub fn write(path: &str, exchanges: &[Exchange]) -> std::io::Result<()> {
+    let entries: Vec<Value> = exchanges.iter().map(|exchange| {
+        let mut request = json!({
+            "method": exchange.method.as_str(),
+            "url": exchange.url,
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": headers(&exchange.request_headers),
+            "queryString": [],
+            "headersSize": -1,
+            "bodySize": exchange.request_body.len(),
+        });
+        if !exchange.request_body.is_empty() {
+            request["postData"] = json!({
+                "mimeType": exchange.request_headers.get("content-type").and_then(|value| value.to_str().ok()).unwrap_or(""),
+                "text": String::from_utf8_lossy(&exchange.request_body),
+            });
+        }
+        let comment: Vec<String> = exchange.failures.iter().map(|failure| format!("Failed: {}", failure)).collect();
+
+        json!({
+            "startedDateTime": timestamp(exchange.started),
+            "time": exchange.time.as_secs_f64() * 1000.0,
+            "request": request,
+            "response": {
+                "status": exchange.status,
+                "statusText": hyper::StatusCode::from_u16(exchange.status).ok().and_then(|status| status.canonical_reason()).unwrap_or(""),
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": headers(&exchange.response_headers),
+                "content": {
+                    "size": exchange.response_body.len(),
+                    "mimeType": exchange.response_headers.get("content-type").and_then(|value| value.to_str().ok()).unwrap_or(""),
+                    "text": String::from_utf8_lossy(&exchange.response_body),
+                },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": exchange.response_body.len(),
+            },
+            "cache": {},
+            "timings": { "send": 0, "wait": exchange.time.as_secs_f64() * 1000.0, "receive": 0 },
+            "comment": format!("{}{}", exchange.group, comment.iter().map(|line| format!("\n{}", line)).collect::<String>()),
+        })
+    }).collect();
+
+    let log = json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "auth-spec-checks", "version": env!("CARGO_PKG_VERSION") },
+            "entries": entries,
+        },
+    });
+    std::fs::write(path, serde_json::to_string_pretty(&log).unwrap())
+}
+
+/// Re-sends every request in a HAR file against `config.base_url`, in order,
+/// and reports where the status differs from the recording. Token secrets,
+/// token ids and test emails from the recording are swapped for the ones
+/// this server hands out, so a sequence that logs in keeps working.
+pub async fn replay(config: &Config) -> bool {
+    let path = config.har.as_ref().unwrap();
+    let log: Value = match std::fs::read(path).map_err(|e| e.to_string())
+        .and_then(|file| serde_json::from_slice(&file).map_err(|e| e.to_string())) {
+        Ok(log) => log,
+        Err(e) => {
+            println!("could not read '{}': {}", path, e);
+            return false
+        }
+    };
+    let entries = log["log"]["entries"].as_array().cloned().unwrap_or_default();
+
+    let client = hyper::Client::new();
+    let mut replacements: Vec<(String, String)> = vec![];
+    let mut differed = 0;
+    for (i, entry) in entries.iter().enumerate() {
+        let request = &entry["request"];
+        let method = request["method"].as_str().unwrap_or("GET");
+        let url = request["url"].as_str().unwrap_or("");
+        let path = url.splitn(4, '/').nth(3).map(|path| format!("/{}", path)).unwrap_or_else(|| "/".into());
+        let body = request["postData"]["text"].as_str().unwrap_or("").to_string();
+
+        new_emails(&mut replacements, &body);
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(format!("{}{}", config.base_url, replace(&replacements, &path)));
+        for header in request["headers"].as_array().into_iter().flatten() {
+            let name = header["name"].as_str().unwrap_or("");
+            if name.eq_ignore_ascii_case("host") || name.eq_ignore_ascii_case("content-length") {
+                continue
+            }
+            builder = builder.header(name, replace(&replacements, header["value"].as_str().unwrap_or("")));
+        }
+
+        let response = match client.request(builder.body(Body::from(replace(&replacements, &body))).unwrap()).await {
+            Ok(response) => response,
+            Err(e) => {
+                println!("{} {} {} - {}", i + 1, method, path, e);
+                return false
+            }
+        };
+        let status = response.status().as_u16();
+        let response_body = hyper::body::to_bytes(response.into_body()).await.unwrap_or_default();
+
+        let recorded_status = entry["response"]["status"].as_u64().unwrap_or(0) as u16;
+        let recorded_json: Value = serde_json::from_str(entry["response"]["content"]["text"].as_str().unwrap_or(""))
+            .unwrap_or(Value::Null);
+        let json: Value = serde_json::from_slice(&response_body).unwrap_or(Value::Null);
+        for name in &["secret", "id"] {
+            if let (Some(recorded), Some(value)) = (recorded_json[name].as_str(), json[name].as_str()) {
+                if recorded != value && !replacements.iter().any(|(from, _)| from == recorded) {
+                    replacements.push((recorded.to_string(), value.to_string()));
+                }
+            }
+        }
+
+        let marker = if status == recorded_status { " " } else { "!" };
+        println!("{}{:>4} {} {} - recorded {}, now {}", marker, i + 1, method, path, recorded_status, status);
+        if status != recorded_status {
+            differed += 1;
+            println!("        {}", String::from_utf8_lossy(&response_body));
+        }
+        for line in entry["comment"].as_str().unwrap_or("").lines().filter(|line| line.starts_with("Failed:")) {
+            println!("        recorded {}", line);
+        }
+    }
+
+    println!("\n{} Replayed / {} Differed", entries.len(), differed);
+    differed == 0
+}
+
+fn headers(headers: &HeaderMap) -> Vec<Value> {
+    headers.iter()
+        .map(|(name, value)| json!({ "name": name.as_str(), "value": String::from_utf8_lossy(value.as_bytes()) }))
+        .collect()
+}
+
+/// Replaces recorded values with the ones from this run, in one pass and
+/// only where they're a whole value, as the repro script does. Emails are
+/// matched ignoring case, as servers compare them.
+fn replace(replacements: &[(String, String)], text: &str) -> String {
+    let mut replaced = String::new();
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let matches = |found: &str, value: &str| if value.contains('@') { found.eq_ignore_ascii_case(value) } else { found == value };
+        match whole_value(text, i, replacements, matches) {
+            Some((prefix_length, value_length, to)) => {
+                replaced += &rest[..prefix_length];
+                replaced += to;
+                i += prefix_length + value_length;
+            }
+            None => {
+                let c = rest.chars().next().unwrap();
+                replaced.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+    replaced
+}
+
+/// The checks register `test+<8 hex digits>@example.com` identities; give
+/// each one a fresh address so replaying doesn't hit "email in use".
+fn new_emails(replacements: &mut Vec<(String, String)>, text: &str) {
+    const EMAIL_LENGTH: usize = "test+00000000@example.com".len();
+    let lowercase = text.to_ascii_lowercase();
+    for (start, _) in lowercase.match_indices("test+") {
+        let email = match lowercase.get(start..start + EMAIL_LENGTH) {
+            Some(email) if email[5..13].chars().all(|c| c.is_ascii_hexdigit()) && email.ends_with("@example.com") => email,
+            _ => continue,
+        };
+        if !replacements.iter().any(|(from, _)| from == email) {
+            replacements.push((email.to_string(), new_email()));
+        }
+    }
+}
+
+/// RFC 3339 UTC timestamp, as HAR requires.
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (days, seconds_of_day) = ((seconds / 86_400) as i64, seconds % 86_400);
+
+    // days to civil date, from Howard Hinnant's date algorithms
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year, month, day,
+        seconds_of_day / 3_600, seconds_of_day % 3_600 / 60, seconds_of_day % 60,
+        since_epoch.subsec_millis(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{new_emails, replace, timestamp};
+
+    #[test]
+    fn timestamp_is_rfc_3339_utc() {
+        assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        assert_eq!(timestamp(UNIX_EPOCH + Duration::from_millis(784_111_777_123)), "1994-11-06T08:49:37.123Z");
+        assert_eq!(timestamp(UNIX_EPOCH + Duration::from_secs(951_825_600)), "2000-02-29T12:00:00.000Z");
+        assert_eq!(timestamp(UNIX_EPOCH + Duration::from_secs(4_107_542_399)), "2100-02-28T23:59:59.000Z");
+    }
+
+    #[test]
+    fn replace_only_replaces_whole_values() {
+        let replacements = vec![("1".to_string(), "7".to_string()), ("abc".to_string(), "xyz".to_string())];
+        assert_eq!(replace(&replacements, "/tokens/1"), "/tokens/7");
+        assert_eq!(replace(&replacements, "/tokens/12"), "/tokens/12");
+        assert_eq!(replace(&replacements, "/v1/tokens"), "/v1/tokens");
+        assert_eq!(replace(&replacements, "Bearer abc"), "Bearer xyz");
+        assert_eq!(replace(&replacements, "token=abc; csrf_token=abc"), "token=xyz; csrf_token=abc");
+        assert_eq!(replace(&replacements, r#"{"id":"1","secret":"abcd","1":"x"}"#), r#"{"id":"7","secret":"abcd","1":"x"}"#);
+    }
+
+    #[test]
+    fn replace_does_not_chain_replacements() {
+        let replacements = vec![("a".to_string(), "b".to_string()), ("b".to_string(), "c".to_string())];
+        assert_eq!(replace(&replacements, "/a/b"), "/b/c");
+    }
+
+    #[test]
+    fn new_emails_match_ignoring_case() {
+        let mut replacements = vec![];
+        new_emails(&mut replacements, r#"{"email":"test+0000abcd@example.com"}"#);
+        new_emails(&mut replacements, r#"{"email":"TEST+0000ABCD@Example.com"}"#);
+        assert_eq!(replacements.len(), 1);
+        let to = &replacements[0].1;
+        assert_eq!(replace(&replacements, r#"{"email":"TEST+0000ABCD@Example.com"}"#), format!(r#"{{"email":"{}"}}"#, to));
+    }
+}
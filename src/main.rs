@@ -1,20 +1,108 @@
 mod checker;
 mod checks {
+    pub mod bearer;
+    pub mod cookies;
+    pub mod cors;
+    pub mod csrf;
+    pub mod emails;
+    pub mod enumeration;
+    pub mod leaks;
+    pub mod methods;
+    pub mod passwordchange;
+    pub mod passwordpolicy;
+    pub mod passwordstorage;
+    pub mod ratelimit;
+    pub mod timing;
     pub mod tokens;
     pub mod tokenscurrent;
     pub mod tokenscurrentrefresh;
+    pub mod tokensecrets;
     pub mod users;
+    pub mod userscurrent;
 }
+mod compare;
+mod config;
+mod coverage;
+mod db;
+mod discovery;
+mod groups;
+mod har;
+mod openapi;
+mod repro;
+mod schema;
+mod spec;
 
 #[tokio::main]
 async fn main() {
-    let mut c = checker::Checker::new("http://localhost:3000".into());
+    let config = config::Config::from_args();
+    match config.command {
+        config::Command::Check => {}
+        config::Command::OpenApi => {
+            println!("{}", serde_json::to_string_pretty(&openapi::document()).unwrap());
+            return
+        }
+        config::Command::Coverage => {
+            if !coverage::report() {
+                std::process::exit(1);
+            }
+            return
+        }
+        config::Command::Compare => {
+            let ok = if config.differential {
+                compare::differential(&config).await
+            } else {
+                compare::matrix(&config).await
+            };
+            if !ok {
+                std::process::exit(1);
+            }
+            return
+        }
+        config::Command::Replay => {
+            if !har::replay(&config).await {
+                std::process::exit(1);
+            }
+            return
+        }
+    }
 
-    checks::tokens::check(&mut c).await;
-    checks::tokenscurrent::check(&mut c).await;
-    checks::tokenscurrentrefresh::check(&mut c).await;
-    checks::users::check(&mut c).await;
+    let mut c = checker::Checker::new(config);
+    discovery::discover(&mut c).await;
+    let target = spec::target_name(c.config.spec_version, &c.config.profiles);
 
-    println!("\n{} Passed / {} Failed", c.passed, c.failed);
-}
+    // a profile without checks yet can't be said to conform
+    for profile in c.config.profiles.clone() {
+        if !groups::GROUPS.iter().any(|group| group.profile == profile && group.is_selected(c.config.spec_version, &c.config.profiles)) {
+            c.skip(profile.name(), "no checks for this profile in this spec version");
+        }
+    }
+
+    for group in groups::GROUPS {
+        if group.is_selected(c.config.spec_version, &c.config.profiles) {
+            groups::run(group, &mut c).await;
+        }
+    }
 
+    if let Some(path) = &c.config.har {
+        match har::write(path, &c.exchanges) {
+            Ok(()) => println!("\nRecorded {} requests to {}", c.exchanges.len(), path),
+            Err(e) => println!("\nCould not write {}: {}", path, e),
+        }
+    }
+    if let Some(dir) = &c.config.repro {
+        match repro::write(dir, &c.config.base_url, &c.exchanges) {
+            Ok(written) => println!("\nWrote {} reproduction scripts to {}", written, dir),
+            Err(e) => println!("\nCould not write to {}: {}", dir, e),
+        }
+    }
+
+    println!("\n{} Passed / {} Failed / {} Skipped", c.passed, c.failed, c.skipped);
+    if c.failed == 0 && c.skipped == 0 {
+        println!("Conforms to {}", target);
+    } else if c.failed == 0 {
+        println!("Conforms to {} ({} skipped)", target, c.skipped);
+    } else {
+        println!("Does not conform to {}", target);
+        std::process::exit(1);
+    }
+}
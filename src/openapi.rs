@@ -0,0 +1,468 @@
+use hyper::Method;
+use serde_json::{json, Value};
+
+use crate::spec::Version;
+
+/// OpenAPI 3 description of the API the checks enforce. Keep in step with
+/// checks.txt and `src/checks/` when the spec changes.
+pub fn document() -> Value {
+    let mut document = json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "auth-spec",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Email and password identities with cookie based tokens.",
+        },
+        "paths": {
+            "/.well-known/auth-spec": {
+                "get": {
+                    "summary": "Capabilities of this implementation (optional)",
+                    "responses": {
+                        "200": json_response("Capabilities", "the spec version, profiles and limits implemented"),
+                        "404": { "description": "no capability document, clients use their defaults" },
+                    },
+                },
+            },
+            "/users": {
+                "post": {
+                    "summary": "Register an identity",
+                    "requestBody": json_body("NewIdentity"),
+                    "responses": {
+                        "200": json_response("Identity", "identity created"),
+                        "400": error_response("missing body, unparseable json, invalid email or password, or email in use"),
+                        "415": error_response("content-type other than application/json"),
+                    },
+                },
+            },
+            "/users/current": {
+                "x-since": "v3",
+                "get": {
+                    "summary": "The identity the current token belongs to",
+                    "security": token_security(),
+                    "responses": {
+                        "200": json_response("Identity", "the current identity"),
+                        "401": error_response("missing, invalid or expired token"),
+                    },
+                },
+                "delete": {
+                    "summary": "Delete the identity and all its tokens",
+                    "security": token_security(),
+                    "responses": {
+                        "200": json_response("Success", "identity and tokens deleted and cookie cleared"),
+                        "401": error_response("missing, invalid or expired token"),
+                        "403": cross_site(),
+                    },
+                },
+            },
+            "/users/current/password": {
+                "x-since": "v3",
+                "put": {
+                    "summary": "Change the identity's password",
+                    "security": token_security(),
+                    "requestBody": json_body("PasswordChange"),
+                    "responses": {
+                        "200": json_response("Success", "password changed and the identity's other tokens deleted"),
+                        "400": error_response("missing body, unparseable json, invalid fields, or wrong current password"),
+                        "401": error_response("missing, invalid or expired token"),
+                        "403": cross_site(),
+                        "415": error_response("content-type other than application/json"),
+                    },
+                },
+            },
+            "/tokens": {
+                "post": {
+                    "summary": "Log in, creating a token",
+                    "requestBody": json_body("NewToken"),
+                    "responses": {
+                        "200": {
+                            "description": "token created, the secret is only ever returned here",
+                            "headers": {
+                                "Set-Cookie": {
+                                    "description": "token=<secret>; HttpOnly; Secure; SameSite; Path=/, and csrf=<value> with double-submit csrf protection",
+                                    "schema": { "type": "string" },
+                                },
+                            },
+                            "content": json_content("TokenWithSecret"),
+                        },
+                        "400": error_response("missing body, unparseable json, invalid fields, or invalid credentials"),
+                        "415": error_response("content-type other than application/json"),
+                        "429": {
+                            "description": "too many failed attempts for this email (rate limiting profile)",
+                            "headers": {
+                                "Retry-After": {
+                                    "description": "seconds until another attempt is allowed",
+                                    "schema": { "type": "integer" },
+                                },
+                            },
+                            "content": error_content(),
+                        },
+                    },
+                },
+                "get": {
+                    "summary": "List the identity's active tokens",
+                    "security": token_security(),
+                    "responses": {
+                        "200": json_response("TokenList", "active tokens, including the current one"),
+                        "401": error_response("missing, invalid or expired token"),
+                    },
+                },
+            },
+            "/tokens/current": {
+                "get": {
+                    "summary": "Get the current token",
+                    "security": token_security(),
+                    "responses": {
+                        "200": json_response("Token", "the token used for this request"),
+                        "401": error_response("missing, invalid or expired token"),
+                    },
+                },
+                "delete": {
+                    "summary": "Log out, deleting the current token",
+                    "security": token_security(),
+                    "responses": {
+                        "200": json_response("Success", "token deleted and cookie cleared"),
+                        "401": error_response("missing, invalid or expired token"),
+                        "403": cross_site(),
+                    },
+                },
+            },
+            "/tokens/current/valid": {
+                "get": {
+                    "summary": "Check the current token is valid",
+                    "security": token_security(),
+                    "responses": {
+                        "200": { "description": "token is valid, empty body" },
+                        "401": error_response("missing, invalid or expired token"),
+                    },
+                },
+            },
+            "/tokens/current/refresh": {
+                "post": {
+                    "summary": "Mark the current token active",
+                    "security": token_security(),
+                    "responses": {
+                        "200": json_response("Token", "the refreshed token"),
+                        "401": error_response("missing, invalid or expired token"),
+                        "403": cross_site(),
+                    },
+                },
+            },
+            "/tokens/{id}": {
+                "parameters": [{
+                    "name": "id",
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" },
+                }],
+                "get": {
+                    "summary": "Get one of the identity's tokens",
+                    "security": token_security(),
+                    "responses": {
+                        "200": json_response("Token", "the token"),
+                        "400": error_response("token belongs to another identity"),
+                        "401": error_response("missing, invalid or expired token"),
+                        "404": error_response("invalid or expired token id"),
+                    },
+                },
+                "delete": {
+                    "summary": "Delete one of the identity's other tokens",
+                    "security": token_security(),
+                    "responses": {
+                        "200": json_response("Success", "token deleted"),
+                        "400": error_response("id is the current token, or belongs to another identity"),
+                        "401": error_response("missing, invalid or expired token"),
+                        "403": cross_site(),
+                        "404": error_response("invalid or expired token id"),
+                    },
+                },
+            },
+        },
+        "components": {
+            "securitySchemes": {
+                "tokenCookie": {
+                    "type": "apiKey",
+                    "in": "cookie",
+                    "name": "token",
+                },
+                "bearerToken": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "bearer profile; takes precedence over the cookie when both are sent",
+                },
+            },
+            "schemas": {
+                "Capabilities": {
+                    "type": "object",
+                    "required": ["spec", "profiles"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "spec": { "type": "string", "enum": ["v1", "v2", "v3"] },
+                        "profiles": {
+                            "type": "array",
+                            "items": {
+                                "type": "string",
+                                "enum": ["core", "cookies", "rate-limiting", "test-clock", "db-inspection", "problem-json", "cors", "csrf", "bearer", "password-policy"],
+                            },
+                        },
+                        "email_max_length": { "type": "integer" },
+                        "password_min_length": { "type": "integer" },
+                        "password_max_length": { "type": "integer" },
+                        "lifetimes": {
+                            "type": "array",
+                            "items": { "$ref": "#/components/schemas/Lifetime" },
+                        },
+                        "token_limit": { "type": "integer", "nullable": true },
+                        "csrf": { "type": "string", "enum": ["origin", "header", "double-submit"] },
+                        "cors_origins": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                        },
+                        "rate_limit": {
+                            "type": "object",
+                            "required": ["attempts", "window_seconds"],
+                            "additionalProperties": false,
+                            "properties": {
+                                "attempts": { "type": "integer" },
+                                "window_seconds": { "type": "integer" },
+                            },
+                        },
+                    },
+                },
+                "NewIdentity": {
+                    "type": "object",
+                    "required": ["email", "password"],
+                    "properties": {
+                        "email": {
+                            "type": "string",
+                            "maxLength": 150,
+                            "description": "v3: one @ with text either side and no whitespace, after trimming surrounding whitespace; unique ignoring case",
+                        },
+                        "password": {
+                            "type": "string",
+                            "description": "password-policy: NFC normalized, then between the capability document's length limits in code points and not breached",
+                        },
+                    },
+                },
+                "PasswordChange": {
+                    "type": "object",
+                    "required": ["current_password", "new_password"],
+                    "properties": {
+                        "current_password": { "type": "string" },
+                        "new_password": {
+                            "type": "string",
+                            "description": "same rules as a new identity's password",
+                        },
+                        "keep_current_token": {
+                            "type": "boolean",
+                            "description": "false also deletes the token used for the request (default true)",
+                        },
+                    },
+                },
+                "Identity": {
+                    "type": "object",
+                    "required": ["id", "email"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "id": { "type": "integer" },
+                        "email": { "type": "string" },
+                    },
+                },
+                "NewToken": {
+                    "type": "object",
+                    "required": ["email", "password", "lifetime"],
+                    "properties": {
+                        "email": {
+                            "type": "string",
+                            "maxLength": 150,
+                            "description": "v3: matched ignoring case and surrounding whitespace",
+                        },
+                        "password": { "type": "string" },
+                        "lifetime": { "$ref": "#/components/schemas/Lifetime" },
+                    },
+                },
+                "Lifetime": {
+                    "type": "string",
+                    "enum": ["until-idle", "remember-me", "no-expiration"],
+                },
+                "Token": {
+                    "type": "object",
+                    "required": ["id", "lifetime", "created", "last_active"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "id": { "type": "string" },
+                        "lifetime": { "$ref": "#/components/schemas/Lifetime" },
+                        "created": { "type": "integer", "format": "int64" },
+                        "last_active": { "type": "integer", "format": "int64" },
+                    },
+                },
+                "TokenWithSecret": {
+                    "type": "object",
+                    "required": ["id", "secret", "lifetime", "created", "last_active"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "id": { "type": "string" },
+                        "secret": { "type": "string" },
+                        "lifetime": { "$ref": "#/components/schemas/Lifetime" },
+                        "created": { "type": "integer", "format": "int64" },
+                        "last_active": { "type": "integer", "format": "int64" },
+                    },
+                },
+                "TokenList": {
+                    "type": "object",
+                    "required": ["tokens"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "tokens": {
+                            "type": "array",
+                            "items": { "$ref": "#/components/schemas/Token" },
+                        },
+                    },
+                },
+                "Success": {
+                    "type": "object",
+                    "required": ["success"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "success": { "type": "string" },
+                    },
+                },
+                "Error": {
+                    "type": "object",
+                    "required": ["error"],
+                    "properties": {
+                        "error": { "type": "string", "description": "human readable, may be reworded or localized" },
+                        "code": { "$ref": "#/components/schemas/ErrorCode" },
+                    },
+                },
+                "Problem": {
+                    "type": "object",
+                    "description": "RFC 7807 problem details, for every error with the problem-json profile",
+                    "required": ["type", "title", "status"],
+                    "properties": {
+                        "type": {
+                            "type": "string",
+                            "description": "URI whose last path segment is the ErrorCode, or about:blank",
+                        },
+                        "title": { "type": "string" },
+                        "status": { "type": "integer", "description": "the response's HTTP status" },
+                        "detail": { "type": "string" },
+                        "instance": { "type": "string" },
+                        "code": { "$ref": "#/components/schemas/ErrorCode" },
+                    },
+                },
+                "ErrorCode": {
+                    "type": "string",
+                    "description": "stable error case, optional; checks match it exactly instead of the error message",
+                    "enum": [
+                        "body_missing",
+                        "content_type_unsupported",
+                        "json_invalid",
+                        "email_missing",
+                        "email_not_string",
+                        "email_too_long",
+                        "email_invalid",
+                        "email_in_use",
+                        "password_missing",
+                        "password_not_string",
+                        "password_too_short",
+                        "password_too_long",
+                        "password_breached",
+                        "current_password_missing",
+                        "current_password_not_string",
+                        "current_password_invalid",
+                        "new_password_missing",
+                        "new_password_not_string",
+                        "lifetime_missing",
+                        "lifetime_not_string",
+                        "lifetime_invalid",
+                        "credentials_invalid",
+                        "rate_limited",
+                        "csrf_rejected",
+                        "method_not_allowed",
+                        "token_invalid",
+                        "token_not_found",
+                        "token_not_owned",
+                        "token_is_current",
+                    ],
+                },
+            },
+        },
+    });
+
+    // a 405 comes from a method a path doesn't list, so it's described once
+    // per path rather than as a response of the operations that are listed
+    for (template, path_item) in document["paths"].as_object_mut().unwrap() {
+        if !template.starts_with("/.well-known/") {
+            path_item["x-method-not-allowed"] = method_not_allowed();
+        }
+    }
+    document
+}
+
+/// The spec version that added a path, from its `x-since` (v1 if absent).
+pub fn since(path_item: &Value) -> Version {
+    path_item["x-since"].as_str().and_then(Version::parse).unwrap_or(Version::V1)
+}
+
+/// The methods a path has operations for.
+pub fn methods(path_item: &Value) -> Vec<Method> {
+    [Method::GET, Method::PUT, Method::POST, Method::DELETE, Method::OPTIONS, Method::HEAD, Method::PATCH, Method::TRACE]
+        .iter()
+        .filter(|method| path_item.get(method.as_str().to_lowercase()).is_some())
+        .cloned()
+        .collect()
+}
+
+/// The cookie, or the bearer header with the bearer profile.
+fn token_security() -> Value {
+    json!([{ "tokenCookie": [] }, { "bearerToken": [] }])
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn json_content(schema: &str) -> Value {
+    json!({ "application/json": { "schema": schema_ref(schema) } })
+}
+
+fn json_body(schema: &str) -> Value {
+    json!({ "required": true, "content": json_content(schema) })
+}
+
+fn json_response(schema: &str, description: &str) -> Value {
+    json!({ "description": description, "content": json_content(schema) })
+}
+
+/// With the csrf profile, depending on the protection: an origin or referer
+/// other than the server's, no x-requested-with header, or an x-csrf-token
+/// header that doesn't match the csrf cookie set on login.
+fn cross_site() -> Value {
+    error_response("cross-site request rejected (csrf profile)")
+}
+
+/// Errors are `Error` json, or `Problem` with the problem-json profile.
+fn error_content() -> Value {
+    json!({
+        "application/json": { "schema": schema_ref("Error") },
+        "application/problem+json": { "schema": schema_ref("Problem") },
+    })
+}
+
+/// The response to any method a path doesn't list, under the path's
+/// `x-method-not-allowed`. OPTIONS (204) and HEAD (when GET is listed)
+/// aren't declared per path but are checked on every one.
+fn method_not_allowed() -> Value {
+    let mut response = error_response("method not allowed");
+    response["headers"] = json!({
+        "Allow": {
+            "description": "the methods the path lists, optionally with HEAD and OPTIONS",
+            "schema": { "type": "string" },
+        },
+    });
+    response
+}
+
+fn error_response(description: &str) -> Value {
+    json!({ "description": description, "content": error_content() })
+}
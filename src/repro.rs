@@ -0,0 +1,299 @@
+use hyper::Method;
+
+use crate::har::Exchange;
+
+/// Writes a shell script per failed exchange to `dir`, returning how many.
+/// Each script re-creates the identity and token the failing request relied
+/// on with curl, captures the new secret, then sends the failing request.
+pub fn write(dir: &str, base_url: &str, exchanges: &[Exchange]) -> std::io::Result<usize> {
+    std::fs::create_dir_all(dir)?;
+    let mut written = 0;
+    for (i, exchange) in exchanges.iter().enumerate() {
+        if exchange.failures.is_empty() {
+            continue
+        }
+        let path = exchange.url.splitn(4, '/').nth(3).unwrap_or("");
+        let slug: String = path.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '-' }).collect();
+        let file = format!("{}/{:03}-{}-{}.sh", dir, i + 1, exchange.method.as_str().to_lowercase(), slug.trim_matches('-'));
+        std::fs::write(&file, script(base_url, exchanges, i))?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+fn script(base_url: &str, exchanges: &[Exchange], failed: usize) -> String {
+    let exchange = &exchanges[failed];
+    let bearer = exchange.request_headers.get("authorization")
+        .and_then(|authorization| authorization.to_str().ok())
+        .and_then(|authorization| authorization.get(7..).filter(|_| authorization[..7].eq_ignore_ascii_case("bearer ")));
+    let secret = exchange.request_headers.get("cookie")
+        .and_then(|cookie| cookie.to_str().ok())
+        .and_then(|cookie| cookie.split(';').find_map(|pair| pair.trim().strip_prefix("token=")))
+        .or(bearer)
+        .map(|secret| secret.to_string());
+
+    // the login that handed out the secret, and the registrations before it
+    let login = secret.as_ref().and_then(|secret| (0..failed).find(|&i| {
+        is_create(&exchanges[i], "/tokens") && json(&exchanges[i].response_body)["secret"].as_str() == Some(secret)
+    }));
+    let mut emails: Vec<String> = vec![];
+    for i in login.into_iter().chain(Some(failed)) {
+        if let Some(email) = json(&exchanges[i].request_body)["email"].as_str() {
+            emails.push(email.to_string());
+        }
+    }
+    emails.dedup();
+    let mut steps: Vec<usize> = emails.iter().filter_map(|email| (0..failed).find(|&i| {
+        is_create(&exchanges[i], "/users") && json(&exchanges[i].request_body)["email"].as_str() == Some(email)
+    })).collect();
+    steps.extend(login);
+    steps.sort_unstable();
+    steps.dedup();
+
+    let mut variables: Vec<(String, String)> = vec![];
+    let mut lines = vec![
+        "#!/bin/sh".to_string(),
+        format!("# {} {} - {}", exchange.method, exchange.url, exchange.group),
+    ];
+    lines.extend(exchange.failures.iter().map(|failure| format!("# Failed: {}", failure)));
+    lines.push("#".into());
+    lines.push("# Reuses the run's emails, so point it at a fresh database or change EMAIL_* below.".into());
+    lines.push(String::new());
+    lines.push(format!("BASE_URL=\"${{BASE_URL:-{}}}\"", base_url));
+    for (n, email) in emails.iter().enumerate() {
+        let name = format!("EMAIL_{}", n + 1);
+        lines.push(format!("{}='{}'", name, email));
+        variables.push((email.clone(), name));
+    }
+    lines.push(String::new());
+
+    for step in steps {
+        let step_exchange = &exchanges[step];
+        lines.push(format!("# {}", step_exchange.group));
+        if Some(step) == login {
+            lines.push(format!("RESPONSE=$({})", curl(step_exchange, &variables, "-s")));
+            lines.push("echo \"$RESPONSE\"".into());
+            lines.push(r#"SECRET=$(echo "$RESPONSE" | sed -n 's/.*"secret" *: *"\([^"]*\)".*/\1/p')"#.into());
+            lines.push(r#"TOKEN_ID=$(echo "$RESPONSE" | sed -n 's/.*"id" *: *"\([^"]*\)".*/\1/p')"#.into());
+            let response = json(&step_exchange.response_body);
+            variables.extend(response["secret"].as_str().map(|secret| (secret.to_string(), "SECRET".to_string())));
+            variables.extend(response["id"].as_str().map(|id| (id.to_string(), "TOKEN_ID".to_string())));
+        } else {
+            lines.push(curl(step_exchange, &variables, "-s"));
+            lines.push("echo".into());
+        }
+        lines.push(String::new());
+    }
+
+    lines.push(format!("# failing request, returned {}", exchange.status));
+    lines.push(curl(exchange, &variables, "-si"));
+    lines.push("echo".into());
+    lines.join("\n") + "\n"
+}
+
+fn curl(exchange: &Exchange, variables: &[(String, String)], flags: &str) -> String {
+    let path = exchange.url.splitn(4, '/').nth(3).map(|path| format!("/{}", path)).unwrap_or_else(|| "/".into());
+    let mut command = format!("curl {} -X {} \"$BASE_URL\"{}", flags, exchange.method, quote(&path, variables));
+    for (name, value) in &exchange.request_headers {
+        let header = format!("{}: {}", name, String::from_utf8_lossy(value.as_bytes()));
+        command += &format!(" -H {}", quote(&header, variables));
+    }
+    if !exchange.request_body.is_empty() {
+        if !exchange.request_headers.contains_key("content-type") {
+            // otherwise curl sends application/x-www-form-urlencoded
+            command += " -H 'content-type:'";
+        }
+        command += &format!(" --data-binary {}", quote(&String::from_utf8_lossy(&exchange.request_body), variables));
+    }
+    command
+}
+
+/// Single quotes `text` for sh, leaving the given values as variables. Only
+/// whole values are replaced: JSON strings, path segments, and the values
+/// after `token=` and `Bearer `, so a short value like a numeric id doesn't
+/// match inside other text.
+fn quote(text: &str, variables: &[(String, String)]) -> String {
+    let mut quoted = String::new();
+    let mut literal = String::new();
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        match whole_value(text, i, variables, |found, value| found == value) {
+            Some((prefix_length, value_length, name)) => {
+                literal += &rest[..prefix_length];
+                quoted += &single_quote(&literal);
+                quoted += &format!("\"${}\"", name);
+                literal.clear();
+                i += prefix_length + value_length;
+            }
+            None => {
+                let c = rest.chars().next().unwrap();
+                literal.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+    quoted += &single_quote(&literal);
+    if quoted.is_empty() {
+        return "''".into()
+    }
+    quoted
+}
+
+/// The longest of `values` that starts after a prefix at `text[i..]` and is
+/// a whole path segment, JSON string value, or cookie or bearer value, with
+/// the prefix's length, the value's length, and what the value maps to.
+pub fn whole_value<'a, T>(
+    text: &str,
+    i: usize,
+    values: &'a [(String, T)],
+    matches: impl Fn(&str, &str) -> bool,
+) -> Option<(usize, usize, &'a T)> {
+    let rest = &text[i..];
+    ["\"", "/", "token=", "Bearer ", "bearer "].iter()
+        .filter(|prefix| rest.starts_with(*prefix) && (!prefix.starts_with(|c: char| c.is_ascii_alphabetic()) || starts_word(text, i)))
+        .find_map(|prefix| {
+            let after_prefix = &rest[prefix.len()..];
+            values.iter()
+                .filter(|(value, _)| !value.is_empty())
+                .filter(|(value, _)| after_prefix.get(..value.len()).is_some_and(|found| matches(found, value)))
+                .filter(|(value, _)| ends_value(prefix, &after_prefix[value.len()..]))
+                .max_by_key(|(value, _)| value.len())
+                .map(|(value, to)| (prefix.len(), value.len(), to))
+        })
+}
+
+fn single_quote(text: &str) -> String {
+    if text.is_empty() {
+        return String::new()
+    }
+    format!("'{}'", text.replace('\'', r#"'\''"#))
+}
+
+/// Whether `text[i..]` isn't the tail of a longer word, such as `csrf_token=`.
+fn starts_word(text: &str, i: usize) -> bool {
+    !text[..i].ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Whether `after` can follow a whole value that came after `prefix`.
+fn ends_value(prefix: &str, after: &str) -> bool {
+    match prefix {
+        // a string value, not an object key
+        "\"" => after.starts_with('"') && !after[1..].trim_start().starts_with(':'),
+        "/" => after.is_empty() || after.starts_with(['/', '?', '#']),
+        _ => after.is_empty() || after.starts_with([';', ',', ' ']),
+    }
+}
+
+fn is_create(exchange: &Exchange, path: &str) -> bool {
+    exchange.method == Method::POST && exchange.url.ends_with(path) && exchange.status == 200
+}
+
+fn json(body: &[u8]) -> serde_json::Value {
+    serde_json::from_slice(body).unwrap_or(serde_json::Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use hyper::header::HeaderValue;
+    use hyper::{HeaderMap, Method};
+
+    use super::*;
+
+    fn variables(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(value, name)| (value.to_string(), name.to_string())).collect()
+    }
+
+    fn exchange(method: Method, path: &str, headers: &[(&'static str, &str)], request_body: &str, status: u16, response_body: &str) -> Exchange {
+        let mut request_headers = HeaderMap::new();
+        for (name, value) in headers {
+            request_headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        Exchange {
+            started: SystemTime::now(),
+            time: Duration::from_millis(1),
+            group: "test",
+            method,
+            url: format!("http://localhost:3000{}", path),
+            request_headers,
+            request_body: request_body.as_bytes().to_vec(),
+            status,
+            response_headers: HeaderMap::new(),
+            response_body: response_body.as_bytes().to_vec(),
+            failures: vec![],
+        }
+    }
+
+    #[test]
+    fn quote_escapes_single_quotes() {
+        assert_eq!(quote("it's", &[]), r#"'it'\''s'"#);
+        assert_eq!(quote("", &[]), "''");
+    }
+
+    #[test]
+    fn quote_replaces_path_segments() {
+        let variables = variables(&[("1", "TOKEN_ID")]);
+        assert_eq!(quote("/tokens/1", &variables), r#"'/tokens/'"$TOKEN_ID""#);
+        assert_eq!(quote("/tokens/1/refresh", &variables), r#"'/tokens/'"$TOKEN_ID"'/refresh'"#);
+        assert_eq!(quote("/tokens/12", &variables), "'/tokens/12'");
+        assert_eq!(quote("/v1/tokens", &variables), "'/v1/tokens'");
+    }
+
+    #[test]
+    fn quote_replaces_whole_json_string_values() {
+        let variables = variables(&[("test+1@example.com", "EMAIL_1"), ("1", "TOKEN_ID")]);
+        assert_eq!(
+            quote(r#"{"email":"test+1@example.com","id":"1","1":"x"}"#, &variables),
+            r#"'{"email":"'"$EMAIL_1"'","id":"'"$TOKEN_ID"'","1":"x"}'"#,
+        );
+        assert_eq!(quote(r#"{"password":"password1"}"#, &variables), r#"'{"password":"password1"}'"#);
+    }
+
+    #[test]
+    fn quote_does_not_replace_inside_inserted_names() {
+        // "EMAIL_" and "1" both appear in "$EMAIL_1" once it's inserted
+        let variables = variables(&[("a@example.com", "EMAIL_1"), ("1", "TOKEN_ID"), ("EMAIL_", "SECRET")]);
+        assert_eq!(quote(r#"{"email":"a@example.com"}"#, &variables), r#"'{"email":"'"$EMAIL_1"'"}'"#);
+    }
+
+    #[test]
+    fn quote_replaces_token_cookie_and_bearer_values() {
+        let variables = variables(&[("abc", "SECRET")]);
+        assert_eq!(quote("cookie: token=abc; other=1", &variables), r#"'cookie: token='"$SECRET"'; other=1'"#);
+        assert_eq!(quote("cookie: csrf_token=abc", &variables), "'cookie: csrf_token=abc'");
+        assert_eq!(quote("authorization: Bearer abc", &variables), r#"'authorization: Bearer '"$SECRET""#);
+        assert_eq!(quote("authorization: Bearer abcd", &variables), "'authorization: Bearer abcd'");
+    }
+
+    #[test]
+    fn script_recreates_the_identity_and_token() {
+        let mut exchanges = [
+            exchange(Method::POST, "/users", &[("content-type", "application/json")], r#"{"email":"a@example.com","password":"pw"}"#, 200, r#"{"id":"7"}"#),
+            exchange(Method::POST, "/tokens", &[("content-type", "application/json")], r#"{"email":"a@example.com","password":"pw"}"#, 200, r#"{"id":"1","secret":"s3cret"}"#),
+            exchange(Method::DELETE, "/tokens/1", &[("cookie", "token=s3cret")], "", 500, ""),
+        ];
+        exchanges[2].failures.push("expected status '200' but got '500'".into());
+
+        let script = script("http://localhost:3000", &exchanges, 2);
+        assert!(script.starts_with("#!/bin/sh\n# DELETE http://localhost:3000/tokens/1 - test\n# Failed: expected status '200' but got '500'\n"));
+        assert!(script.contains("EMAIL_1='a@example.com'\n"));
+        assert!(script.contains(r#"curl -s -X POST "$BASE_URL"'/users' -H 'content-type: application/json' --data-binary '{"email":"'"$EMAIL_1"'","password":"pw"}'"#));
+        assert!(script.contains(r#"RESPONSE=$(curl -s -X POST "$BASE_URL"'/tokens'"#));
+        assert!(script.ends_with(concat!(
+            "# failing request, returned 500\n",
+            r#"curl -si -X DELETE "$BASE_URL"'/tokens/'"$TOKEN_ID" -H 'cookie: token='"$SECRET""#, "\n",
+            "echo\n",
+        )));
+    }
+
+    #[test]
+    fn script_without_a_token_only_registers() {
+        let mut failed = exchange(Method::POST, "/users", &[], r#"{"email":"a@example.com"}"#, 500, "");
+        failed.failures.push("expected status '400' but got '500'".into());
+        let script = script("http://localhost:3000", &[failed], 0);
+        assert!(!script.contains("RESPONSE="));
+        assert!(script.contains(r#"curl -si -X POST "$BASE_URL"'/users' -H 'content-type:' --data-binary '{"email":"'"$EMAIL_1"'"}'"#));
+    }
+}
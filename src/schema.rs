@@ -0,0 +1,213 @@
+use hyper::{Method, StatusCode};
+use serde_json::Value;
+
+/// Finds the schema `document` declares for a response, matching `path`
+/// against the OpenAPI path templates and `media_type` against the declared
+/// content, falling back to the first. Returns `Ok(None)` when the response is
+/// declared without a body.
+pub fn response_schema<'a>(document: &'a Value, method: &Method, path: &str, status: StatusCode, media_type: &str) -> Result<Option<&'a Value>, String> {
+    let template = find_path(document, path).ok_or_else(|| format!("no path in the spec matches '{}'", path))?;
+    let path_item = &document["paths"][template];
+    let operation = &path_item[method.as_str().to_lowercase()];
+
+    let response = if !operation.is_null() {
+        &operation["responses"][status.as_str()]
+    } else if status == StatusCode::METHOD_NOT_ALLOWED {
+        &path_item["x-method-not-allowed"]
+    } else {
+        return Err(format!("spec has no {} operation for '{}'", method, template))
+    };
+    if response.is_null() {
+        return Err(format!("spec has no '{}' response for {} '{}'", status, method, template))
+    }
+    let schema = response["content"]
+        .as_object()
+        .and_then(|content| content.get(media_type).or_else(|| content.values().next()))
+        .map(|media_type| &media_type["schema"]);
+
+    Ok(schema)
+}
+
+/// Checks `value` against `schema`, returning a description of every
+/// mismatch. `strict` also rejects nulls and properties a closed schema
+/// doesn't declare.
+pub fn validate(document: &Value, schema: &Value, value: &Value, strict: bool) -> Vec<String> {
+    let mut errors = vec![];
+    validate_at(document, schema, value, strict, "$", &mut errors);
+    errors
+}
+
+fn validate_at(document: &Value, schema: &Value, value: &Value, strict: bool, at: &str, errors: &mut Vec<String>) {
+    if let Some(reference) = schema["$ref"].as_str() {
+        match resolve(document, reference) {
+            Some(schema) => validate_at(document, schema, value, strict, at, errors),
+            None => errors.push(format!("{}: unresolvable schema reference '{}'", at, reference)),
+        }
+        return
+    }
+
+    if value.is_null() {
+        if strict || schema["nullable"] != Value::Bool(true) {
+            errors.push(format!("{} is null", at));
+        }
+        return
+    }
+
+    let type_matches = match schema["type"].as_str() {
+        Some("object") => value.is_object(),
+        Some("array") => value.is_array(),
+        Some("string") => value.is_string(),
+        Some("integer") => value.is_i64() || value.is_u64(),
+        Some("number") => value.is_number(),
+        Some("boolean") => value.is_boolean(),
+        _ => true,
+    };
+    if !type_matches {
+        errors.push(format!("{} should be {} but is {}", at, schema["type"].as_str().unwrap_or("?"), value));
+        return
+    }
+
+    if let Some(allowed) = schema["enum"].as_array() {
+        if !allowed.contains(value) {
+            errors.push(format!("{} is {} but should be one of {}", at, value, schema["enum"]));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        for name in schema["required"].as_array().into_iter().flatten().filter_map(|name| name.as_str()) {
+            if !object.contains_key(name) {
+                errors.push(format!("{} does not have a '{}' property", at, name));
+            }
+        }
+        for (name, property) in object {
+            let property_at = format!("{}.{}", at, name);
+            match schema["properties"].get(name) {
+                Some(property_schema) => validate_at(document, property_schema, property, strict, &property_at, errors),
+                None if strict && schema["additionalProperties"] == Value::Bool(false) => {
+                    errors.push(format!("{} is not in the spec", property_at));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let Some(items) = value.as_array() {
+        for (i, item) in items.iter().enumerate() {
+            validate_at(document, &schema["items"], item, strict, &format!("{}[{}]", at, i), errors);
+        }
+    }
+}
+
+fn resolve<'a>(document: &'a Value, reference: &str) -> Option<&'a Value> {
+    document.pointer(reference.strip_prefix('#')?)
+}
+
+/// Literal segments win over `{param}` segments, so `/tokens/current` isn't
+/// matched as `/tokens/{id}`.
+fn find_path<'a>(document: &'a Value, path: &str) -> Option<&'a str> {
+    let segments: Vec<&str> = path.split('/').collect();
+    document["paths"]
+        .as_object()?
+        .keys()
+        .filter_map(|template| {
+            let template_segments: Vec<&str> = template.split('/').collect();
+            if template_segments.len() != segments.len() {
+                return None
+            }
+            let mut literal_matches = 0;
+            for (template_segment, segment) in template_segments.iter().zip(&segments) {
+                if template_segment.starts_with('{') {
+                    continue
+                } else if template_segment == segment {
+                    literal_matches += 1;
+                } else {
+                    return None
+                }
+            }
+            Some((template.as_str(), literal_matches))
+        })
+        .max_by_key(|(_, literal_matches)| *literal_matches)
+        .map(|(template, _)| template)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::{find_path, validate};
+
+    fn document() -> Value {
+        json!({
+            "paths": {
+                "/tokens": {},
+                "/tokens/{id}": {},
+                "/tokens/current": {},
+                "/tokens/{id}/refresh": {},
+            },
+            "components": {
+                "schemas": {
+                    "Token": {
+                        "type": "object",
+                        "required": ["id", "expires"],
+                        "additionalProperties": false,
+                        "properties": {
+                            "id": { "type": "string" },
+                            "expires": { "type": "integer", "nullable": true },
+                            "lifetime": { "type": "string", "enum": ["short", "long"] },
+                        },
+                    },
+                },
+            },
+        })
+    }
+
+    #[test]
+    fn find_path_prefers_literal_segments() {
+        let document = document();
+        assert_eq!(find_path(&document, "/tokens"), Some("/tokens"));
+        assert_eq!(find_path(&document, "/tokens/current"), Some("/tokens/current"));
+        assert_eq!(find_path(&document, "/tokens/abc"), Some("/tokens/{id}"));
+        assert_eq!(find_path(&document, "/tokens/abc/refresh"), Some("/tokens/{id}/refresh"));
+        assert_eq!(find_path(&document, "/users"), None);
+        assert_eq!(find_path(&document, "/tokens/abc/other"), None);
+    }
+
+    #[test]
+    fn validate_follows_references() {
+        let document = document();
+        let schema = json!({ "$ref": "#/components/schemas/Token" });
+        assert!(validate(&document, &schema, &json!({ "id": "a", "expires": 1 }), true).is_empty());
+        assert_eq!(
+            validate(&document, &json!({ "$ref": "#/components/schemas/Missing" }), &json!({}), false),
+            vec!["$: unresolvable schema reference '#/components/schemas/Missing'"],
+        );
+    }
+
+    #[test]
+    fn validate_reports_every_mismatch() {
+        let document = document();
+        let schema = json!({ "$ref": "#/components/schemas/Token" });
+        assert_eq!(
+            validate(&document, &schema, &json!({ "id": 1, "lifetime": "forever" }), false),
+            vec![
+                "$ does not have a 'expires' property",
+                "$.id should be string but is 1",
+                "$.lifetime is \"forever\" but should be one of [\"short\",\"long\"]",
+            ],
+        );
+        assert_eq!(validate(&document, &schema, &json!([]), false), vec!["$ should be object but is []"]);
+        assert_eq!(
+            validate(&document, &json!({ "type": "array", "items": { "type": "integer" } }), &json!([1, "2"]), false),
+            vec!["$[1] should be integer but is \"2\""],
+        );
+    }
+
+    #[test]
+    fn validate_strict_rejects_nulls_and_undeclared_properties() {
+        let document = document();
+        let schema = json!({ "$ref": "#/components/schemas/Token" });
+        let value = json!({ "id": "a", "expires": null, "extra": true });
+        assert!(validate(&document, &schema, &value, false).is_empty());
+        assert_eq!(validate(&document, &schema, &value, true), vec!["$.expires is null", "$.extra is not in the spec"]);
+    }
+}
@@ -0,0 +1,96 @@
+/// Revisions of the spec. Each check group declares the version that
+/// introduced it, and a run targets one version and every group up to it.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum Version {
+    /// users, tokens, tokens/current and tokens/current/refresh
+    V1,
+    /// token secret quality, login timing, enumeration, leaks, and the
+    /// optional profiles
+    V2,
+    /// identity self-service under /users/current, email rules, and the
+    /// password-policy profile
+    V3,
+}
+
+pub const LATEST: Version = Version::V3;
+
+impl Version {
+    pub fn name(self) -> &'static str {
+        match self {
+            Version::V1 => "v1",
+            Version::V2 => "v2",
+            Version::V3 => "v3",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Version> {
+        match name {
+            "v1" => Some(Version::V1),
+            "v2" => Some(Version::V2),
+            "v3" => Some(Version::V3),
+            _ => None,
+        }
+    }
+}
+
+/// Optional parts of the spec an implementation can claim on top of core.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Profile {
+    Core,
+    Cookies,
+    RateLimiting,
+    /// no checks yet, so claiming it is reported as skipped
+    TestClock,
+    DbInspection,
+    /// errors are RFC 7807 application/problem+json
+    ProblemJson,
+    /// credentialed cross-origin requests from configured origins
+    Cors,
+    /// cross-site requests can't use the token cookie to change state
+    Csrf,
+    /// tokens also accepted as an authorization: Bearer header
+    Bearer,
+    /// length limits, unicode normalization and an optional blocklist for
+    /// new passwords
+    PasswordPolicy,
+}
+
+pub const PROFILES: &[Profile] = &[
+    Profile::Core,
+    Profile::Cookies,
+    Profile::RateLimiting,
+    Profile::TestClock,
+    Profile::DbInspection,
+    Profile::ProblemJson,
+    Profile::Cors,
+    Profile::Csrf,
+    Profile::Bearer,
+    Profile::PasswordPolicy,
+];
+
+impl Profile {
+    pub fn name(self) -> &'static str {
+        match self {
+            Profile::Core => "core",
+            Profile::Cookies => "cookies",
+            Profile::RateLimiting => "rate-limiting",
+            Profile::TestClock => "test-clock",
+            Profile::DbInspection => "db-inspection",
+            Profile::ProblemJson => "problem-json",
+            Profile::Cors => "cors",
+            Profile::Csrf => "csrf",
+            Profile::Bearer => "bearer",
+            Profile::PasswordPolicy => "password-policy",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Profile> {
+        PROFILES.iter().copied().find(|profile| profile.name() == name)
+    }
+}
+
+/// "v2 core + cookies", for the conformance summary.
+pub fn target_name(version: Version, profiles: &[Profile]) -> String {
+    let profiles: Vec<&str> = profiles.iter().map(|profile| profile.name()).collect();
+    format!("{} {}", version.name(), profiles.join(" + "))
+}